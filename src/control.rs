@@ -1,7 +1,15 @@
-use crate::hard_registers::ICVersion;
-use std::*;
+use crate::hard_registers::{
+    self, ClockSelect, Frequency, HardRegisters, ICVersion, RxADCBw, RxADCTrim, RxFrontend,
+    LowBatteryThreshold, RxPGABw, RxZIn, ThresholdValue, TxFrontend, TxFrontend1255,
+    TxMixerTankResistance, RegisterAddr, ValidationError,
+};
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
 
 #[allow(dead_code)]
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Receive {
     /// Frequency in MHz.
     pub frequency: f64,
@@ -27,10 +35,11 @@ pub struct Receive {
     /// Receive delta-sigma SSB bandwidth, minimum, in KHz.
     pub adc_bw: u16,
 
-    /// Receive programmable gain amplifier bandwidth in dB.
+    /// Receive programmable gain amplifier bandwidth in KHz.
     pub pga_bw: f32,
 
-    /// Receive PLL loop filter bandwidth. bandwidth = (value + 1) * 75 KHz.
+    /// Receive PLL loop filter bandwidth in KHz, 75 to 300 KHz in 75 KHz
+    /// steps.
     /// Wider bandwidth reduces lock time while increasing spurs and noise.
     pub pll_bw: u16,
 
@@ -44,6 +53,9 @@ pub struct Receive {
 }
 
 #[allow(dead_code)]
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Transmit {
     /// Frequency in MHz.
     pub frequency: f64,
@@ -71,7 +83,9 @@ pub struct Transmit {
     dac_bandwidth: u8,
 }
 
-#[derive(Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum LoopBack {
   #[default]
   Off,
@@ -79,7 +93,9 @@ pub enum LoopBack {
   RF,
 }
 
-#[derive(Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Mode {
     #[default]
     Sleep,
@@ -91,20 +107,132 @@ pub enum Mode {
 
 #[doc = include_str!("../markdown/control.md")]
 #[allow(dead_code)]
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Control {
     /// The frequency of the clock crystal. This should be between 32 and 36.864
     /// MHz. For frequency accuracy, this should be measured per device, rather
     /// than simply taken from a specification.
     pub crystal_frequency: f64,
+    /// Which IC this is, it determines the frequency step resolution.
+    pub ic_version: ICVersion,
     pub mode: Mode,
     pub loop_back: LoopBack,
     pub clock_output_enable: bool,
+    /// The supply voltage below which the IC reports a low battery, in volts.
+    /// It is set to the nearest of the SX1257's eight thresholds, 2.516 to
+    /// 3.245 volts. The SX1255 has no such register and ignores it.
     pub battery_lower_limit: f32,
     pub transmit: Transmit,
     pub receive: Receive,
 }
 
+/// Builds a [Control] from the settings that matter to the application,
+/// with the rest at their defaults: a 36 MHz crystal, the reset-default
+/// frequency of the IC, and the LNA at 0 dB, register code 1, where the data
+/// sheet specifies its noise figure.
+#[derive(Debug, Clone)]
+pub struct ControlBuilder {
+    control: Control,
+    receive_frequency_set: bool,
+    transmit_frequency_set: bool,
+}
+
+impl Default for ControlBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ControlBuilder {
+    /// A builder for an SX1255 in sleep mode, with the defaults above.
+    pub fn new() -> ControlBuilder {
+        let mut control = Control { crystal_frequency: 36.0, ..Default::default() };
+        control.receive.lna_gain = 0.0;
+        ControlBuilder { control, receive_frequency_set: false, transmit_frequency_set: false }
+    }
+
+    /// The crystal frequency in MHz.
+    pub fn crystal_frequency(mut self, mhz: f64) -> Self {
+        self.control.crystal_frequency = mhz;
+        self
+    }
+
+    /// The IC the configuration is for, which also sets its RF band and
+    /// frequency step.
+    pub fn ic_version(mut self, ic_version: ICVersion) -> Self {
+        self.control.ic_version = ic_version;
+        self
+    }
+
+    /// The operating mode.
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.control.mode = mode;
+        self
+    }
+
+    /// The receive frequency in MHz. Unset, it is the reset default.
+    pub fn receive_frequency_mhz(mut self, mhz: f64) -> Self {
+        self.control.receive.frequency = mhz;
+        self.receive_frequency_set = true;
+        self
+    }
+
+    /// The transmit frequency in MHz. Unset, it is the reset default.
+    pub fn transmit_frequency_mhz(mut self, mhz: f64) -> Self {
+        self.control.transmit.frequency = mhz;
+        self.transmit_frequency_set = true;
+        self
+    }
+
+    /// The LNA gain in dB: 0 for the highest gain, or one of the lower
+    /// steps of [RxLnaGain](crate::hard_registers::RxLnaGain).
+    pub fn lna_gain_db(mut self, db: f32) -> Self {
+        self.control.receive.lna_gain = db;
+        self
+    }
+
+    /// The baseband amplifier gain in dB.
+    pub fn baseband_gain_db(mut self, db: f32) -> Self {
+        self.control.receive.baseband_gain = db;
+        self
+    }
+
+    /// Fill in the unset frequencies, and check that both are within the RF
+    /// band of the IC. The error names the most significant frequency
+    /// register, and holds the rejected frequency in Hz.
+    pub fn build(self) -> Result<Control, ValidationError> {
+        let mut control = self.control;
+        let ic_version = control.ic_version;
+        let reset_mhz = HardRegisters::default_for(ic_version)
+            .rx
+            .to_hz(control.crystal_frequency * 1e6, ic_version)
+            / 1e6;
+        if !self.receive_frequency_set {
+            control.receive.frequency = reset_mhz;
+        }
+        if !self.transmit_frequency_set {
+            control.transmit.frequency = reset_mhz;
+        }
+
+        let (low, high) = ic_version.rf_range_hz();
+        let check = |register: RegisterAddr, field, mhz: f64| {
+            let hz = mhz * 1e6;
+            if (low..=high).contains(&hz) {
+                Ok(())
+            } else {
+                Err(ValidationError { register: register as u8, field, value: hz as u32 })
+            }
+        };
+        check(RegisterAddr::RxFreqMsb, "receive.frequency", control.receive.frequency)?;
+        check(RegisterAddr::TxFreqMsb, "transmit.frequency", control.transmit.frequency)?;
+        Ok(control)
+    }
+}
+
 #[allow(dead_code)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// SX1255/SX1257 soft status information, decoded from
 /// [hard_registers::Status](crate::hard_registers::Status) and/or digital I/O
 /// lines.
@@ -119,6 +247,107 @@ pub struct Status {
     pub transmit_pll_locked: bool,
 }
 
+impl Status {
+    /// Decode the hardware status register.
+    pub fn from_hard(status: &hard_registers::Status, ic_version: ICVersion) -> Self {
+        Status {
+            ic_version,
+            battery_low: status.eol,
+            oscillator_stable: status.xosc_ready,
+            receive_pll_locked: status.pll_lock_rx,
+            transmit_pll_locked: status.pll_lock_tx,
+        }
+    }
+}
+
+impl Receive {
+    /// Decode the receive settings from registers read from the IC.
+    /// `crystal` is the crystal frequency in MHz.
+    pub fn from_hard_registers(registers: &HardRegisters, crystal: f64, ic_version: ICVersion) -> Self {
+        let rx = &registers.rx_frontend;
+        Receive {
+            frequency: registers.rx.to_hz(crystal * 1e6, ic_version) / 1e6,
+            pll_locked: registers.status.pll_lock_rx,
+            input_impedance: match rx.zin {
+                RxZIn::I50Ω => 50,
+                RxZIn::I200Ω => 200,
+            },
+            lna_gain: RxFrontend::lna_gain_from_code(rx.lna_gain),
+            baseband_gain: RxFrontend::baseband_gain_from_code(rx.baseband_gain),
+            zin: match rx.zin {
+                RxZIn::I50Ω => 0,
+                RxZIn::I200Ω => 1,
+            },
+            adc_bw: rx.adc_bw.min_khz(),
+            pga_bw: rx.pga_bw.khz(),
+            pll_bw: (rx.pll_bw as u16 + 1) * 75,
+            adc_temp: rx.adc_temp,
+        }
+    }
+}
+
+impl Transmit {
+    /// Decode the transmit settings from registers read from the IC.
+    /// `crystal` is the crystal frequency in MHz. The SX1255-only settings
+    /// are left at zero on SX1257.
+    pub fn from_hard_registers(registers: &HardRegisters, crystal: f64, ic_version: ICVersion) -> Self {
+        let mut transmit = Transmit {
+            frequency: registers.tx.to_hz(crystal * 1e6, ic_version) / 1e6,
+            dac_gain: TxFrontend::dac_gain_from_code(registers.tx_frontend.dac_gain),
+            mixer_gain: TxFrontend::mixer_gain_from_code(registers.tx_frontend.mixer_gain),
+            ..Default::default()
+        };
+        if ic_version == ICVersion::SX1255 {
+            let tx = &registers.tx_frontend_1255;
+            transmit.mixer_tank_cap = tx.mixer_tank_cap as u16 * 128;
+            transmit.mixer_tank_res = tx.mixer_tank_resistance.ohms() as f32 / 1e3;
+            transmit.pll_bandwidth = (tx.pll_bw as f32 + 1.0) * 75.0;
+            transmit.filter_bandwidth = tx.filter_bw_mhz();
+            transmit.dac_bandwidth = tx.tap_count();
+        }
+        transmit
+    }
+}
+
+impl Control {
+    /// Decode a whole configuration from registers read from the IC, the
+    /// inverse of [to_hard_registers](Self::to_hard_registers). `crystal` is
+    /// the crystal frequency in MHz. A mode with the oscillator on but
+    /// neither the receiver nor the transmitter is [Mode::Standby].
+    pub fn from_hard_registers(registers: &HardRegisters, crystal: f64, ic_version: ICVersion) -> Self {
+        let hard_mode = &registers.mode;
+        let mode = match (hard_mode.standby_enable, hard_mode.rx_enable, hard_mode.tx_enable) {
+            (false, _, _) => Mode::Sleep,
+            (true, true, true) => Mode::FullDuplex,
+            (true, true, false) => Mode::Receive,
+            (true, false, true) => Mode::Transmit,
+            (true, false, false) => Mode::Standby,
+        };
+        let clock_select = &registers.clock_select;
+        let loop_back = if clock_select.dig_loopback_enable {
+            LoopBack::Digital
+        } else if clock_select.rf_loopback_enable {
+            LoopBack::RF
+        } else {
+            LoopBack::Off
+        };
+        let battery_lower_limit = match ic_version {
+            ICVersion::SX1257 => registers.low_battery_threshold.threshold.volts(),
+            ICVersion::SX1255 => 0.0,
+        };
+        Control {
+            crystal_frequency: crystal,
+            ic_version,
+            mode,
+            loop_back,
+            clock_output_enable: clock_select.clock_output_enable,
+            battery_lower_limit,
+            transmit: Transmit::from_hard_registers(registers, crystal, ic_version),
+            receive: Receive::from_hard_registers(registers, crystal, ic_version),
+        }
+    }
+}
+
 #[allow(dead_code)]
 impl Control {
   /// Calculate the offset from baseband, in Hz, necessary in the SDR software
@@ -128,9 +357,575 @@ impl Control {
   /// requested frequency, and thus this value will be a positive value less
   /// than the resolution of the IC, or zero. The resolution of the IC will be
   /// around 34 Hz, depending on the oscillator crystal.
-  pub const fn offset(_frequency: f64) -> f64 {
-    0.0
+  ///
+  /// `frequency` is in Hz, and should be within the tuning range of the IC.
+  pub fn offset(&self, frequency: f64) -> f64 {
+    let crystal = self.crystal_frequency * 1e6;
+    frequency - Frequency::from_hz(frequency, crystal, self.ic_version).to_hz(crystal, self.ic_version)
   }
 
-  pub fn write() { }
+  /// The FFT bin in which a carrier at `freq_hz` will appear once the SDR
+  /// software has shifted the baseband by [offset](Self::offset), for a
+  /// waterfall or spectrum display. Bin 0 is DC. `sample_rate` is in Hz.
+  /// The residual offset is positive, so the bin counts upward from DC,
+  /// wrapping at `fft_size` the same way the FFT output does. `None` if
+  /// `fft_size` is 0.
+  pub fn offset_bin(&self, freq_hz: f64, fft_size: usize, sample_rate: f64) -> Option<usize> {
+    let bin_width = sample_rate / fft_size as f64;
+    let bin = (self.offset(freq_hz) / bin_width).round() as usize;
+    bin.checked_rem(fft_size)
+  }
+
+  /// Plan low-IF reception. The LO is tuned `if_offset_hz` below
+  /// `desired_hz`, so that the signal lands away from the DC hole, and the
+  /// returned offset in Hz is the NCO shift that the SDR software must
+  /// apply to bring the desired frequency back to baseband. It includes
+  /// the sub-step residual, since the LO is always tuned lower-than or
+  /// equal-to the requested frequency.
+  pub fn low_if_plan(&self, desired_hz: f64, if_offset_hz: f64) -> (Frequency, f64) {
+    let crystal = self.crystal_frequency * 1e6;
+    let frequency = Frequency::from_hz(desired_hz - if_offset_hz, crystal, self.ic_version);
+    (frequency, desired_hz - frequency.to_hz(crystal, self.ic_version))
+  }
+
+  /// Set [crystal_frequency](Self::crystal_frequency) from the nominal
+  /// crystal frequency in MHz and its measured error in ppm, positive when
+  /// the crystal runs fast. Every conversion afterward uses the corrected
+  /// step, so a requested frequency is tuned to within one step of where
+  /// the IC actually lands. See [diagnose_crystal_mismatch] for measuring
+  /// the error, and [CrystalCalibration] for finer corrections.
+  pub fn calibrate_crystal(&mut self, nominal_mhz: f64, measured_ppm_error: f64) {
+    self.crystal_frequency = nominal_mhz * (1.0 + measured_ppm_error * 1e-6);
+  }
+
+  /// Write only what changed since `previous` was written: both are
+  /// compiled with [to_hard_registers](Self::to_hard_registers), and the
+  /// registers that differ are written with
+  /// [write_diff](crate::transport::Sx125x::write_diff). Changing the gains
+  /// for AGC writes the gain register alone, without disturbing the mode or
+  /// the synthesizers.
+  #[cfg(feature = "transport")]
+  pub fn apply_changes<SPI: embedded_hal::spi::SpiDevice>(
+    &self,
+    previous: &Control,
+    transport: &mut crate::transport::Sx125x<SPI>,
+  ) -> Result<(), crate::Error<SPI::Error>> {
+    debug_assert_eq!(self.ic_version, transport.ic_version());
+    debug_assert_eq!(previous.ic_version, self.ic_version);
+    let old = previous.to_hard_registers();
+    let new = self.to_hard_registers();
+    transport.write_diff(&old, &new)
+  }
+
+  /// Compile this configuration into the
+  /// [HardRegisters](crate::hard_registers::HardRegisters) for
+  /// [ic_version](Self::ic_version).
+  /// Values that the IC can't represent exactly are set to the nearest
+  /// legal code, and the SX1255-only transmit settings are left at their
+  /// defaults on SX1257. [battery_lower_limit](Self::battery_lower_limit)
+  /// is only set on SX1257.
+  ///
+  /// Every mode except sleep enables the oscillator with `standby_enable`,
+  /// since the receiver and transmitter don't work without it.
+  pub fn to_hard_registers(&self) -> HardRegisters {
+    let ic_version = self.ic_version;
+    let crystal = self.crystal_frequency * 1e6;
+    let receive = &self.receive;
+    let transmit = &self.transmit;
+
+    let (rx_enable, tx_enable) = match self.mode {
+      Mode::Sleep | Mode::Standby => (false, false),
+      Mode::Receive => (true, false),
+      Mode::Transmit => (false, true),
+      Mode::FullDuplex => (true, true),
+    };
+    let mode = hard_registers::Mode {
+      standby_enable: !matches!(self.mode, Mode::Sleep),
+      rx_enable,
+      tx_enable,
+      driver_enable: tx_enable,
+      ..Default::default()
+    };
+
+    let rx_frontend = RxFrontend {
+      lna_gain: RxFrontend::lna_gain_code(receive.lna_gain),
+      baseband_gain: RxFrontend::baseband_gain_code(receive.baseband_gain),
+      zin: if receive.zin == 0 { RxZIn::I50Ω } else { RxZIn::I200Ω },
+      adc_bw: RxADCBw::optimal_for_channel(receive.adc_bw, ic_version),
+      adc_trim: if self.crystal_frequency < 34.0 { RxADCTrim::XTal32Mhz } else { RxADCTrim::XTal36MHz },
+      pga_bw: pga_bw(receive.pga_bw),
+      pll_bw: pll_bw(receive.pll_bw as f32),
+      adc_temp: receive.adc_temp,
+      ..Default::default()
+    };
+
+    let tx_frontend = TxFrontend {
+      dac_gain: TxFrontend::dac_gain_code(transmit.dac_gain),
+      mixer_gain: TxFrontend::mixer_gain_code(transmit.mixer_gain),
+      ..Default::default()
+    };
+
+    let mut tx_frontend_1255 = TxFrontend1255::default();
+    if ic_version == ICVersion::SX1255 {
+      tx_frontend_1255.mixer_tank_cap = (transmit.mixer_tank_cap as f32 / 128.0).round().min(7.0) as u8;
+      tx_frontend_1255.mixer_tank_resistance =
+        TxMixerTankResistance::nearest((transmit.mixer_tank_res * 1e3).round() as u32);
+      tx_frontend_1255.pll_bw = pll_bw(transmit.pll_bandwidth);
+      tx_frontend_1255.set_filter_bw_mhz(transmit.filter_bandwidth);
+      tx_frontend_1255.set_tap_count(transmit.dac_bandwidth);
+    }
+
+    let mut clock_select = ClockSelect::default();
+    clock_select.dig_loopback_enable = matches!(self.loop_back, LoopBack::Digital);
+    clock_select.rf_loopback_enable = matches!(self.loop_back, LoopBack::RF);
+    clock_select.clock_output_enable = self.clock_output_enable;
+
+    let mut low_battery_threshold = LowBatteryThreshold::default();
+    if ic_version == ICVersion::SX1257 {
+      low_battery_threshold.threshold = ThresholdValue::nearest(self.battery_lower_limit);
+    }
+
+    HardRegisters {
+      mode,
+      rx: Frequency::from_hz(receive.frequency * 1e6, crystal, ic_version),
+      tx: Frequency::from_hz(transmit.frequency * 1e6, crystal, ic_version),
+      tx_frontend,
+      tx_frontend_1255,
+      rx_frontend,
+      clock_select,
+      low_battery_threshold,
+      ..Default::default()
+    }
+  }
+}
+
+/// The PGA bandwidth setting nearest to `khz`.
+fn pga_bw(khz: f32) -> RxPGABw {
+  [RxPGABw::BW1500KHz, RxPGABw::BW1000KHz, RxPGABw::BW750KHz, RxPGABw::BW500KHz]
+    .into_iter()
+    .min_by(|a, b| (khz - a.khz()).abs().total_cmp(&(khz - b.khz()).abs()))
+    .unwrap()
+}
+
+/// The PLL loop filter bandwidth code nearest to `khz`, (code + 1) * 75 KHz.
+fn pll_bw(khz: f32) -> u8 {
+  (khz / 75.0 - 1.0).round().clamp(0.0, 3.0) as u8
+}
+
+/// Quantify a crystal error from its symptom: a signal that shows up on
+/// the wrong frequency. `expected_hz` is the crystal frequency, in Hz,
+/// that was used to compute `register_value`, and `measured_rf_hz` is where
+/// the IC actually tuned, as read on a frequency counter or a known signal.
+/// The RF frequency scales directly with the crystal, so the result is the
+/// implied crystal error in ppm, positive when the crystal runs fast. Feed
+/// it back into the crystal calibration to correct tuning.
+pub fn diagnose_crystal_mismatch(
+    expected_hz: f64,
+    measured_rf_hz: f64,
+    register_value: u32,
+    ic_version: ICVersion,
+) -> f64 {
+    let expected_rf = Frequency::from_raw(register_value).to_hz(expected_hz, ic_version);
+    (measured_rf_hz / expected_rf - 1.0) * 1e6
+}
+
+/// The oscillator frequency as corrected against a reference, such as a
+/// GPS-disciplined oscillator. Tune through it, with
+/// [frequency](Self::frequency), and every correction is applied.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CrystalCalibration {
+    /// The corrected oscillator frequency in Hz.
+    pub oscillator_hz: f64,
+}
+
+impl CrystalCalibration {
+    /// Start from the nominal, or last measured, oscillator frequency in Hz.
+    pub fn new(oscillator_hz: f64) -> CrystalCalibration {
+        CrystalCalibration { oscillator_hz }
+    }
+
+    /// Apply a measured oscillator error in parts per billion, positive when
+    /// the oscillator runs fast. Errors accumulate, so each measurement
+    /// should be taken while tuned with the current calibration.
+    pub fn discipline(&mut self, measured_ppb: f64) {
+        self.oscillator_hz *= 1.0 + measured_ppb * 1e-9;
+    }
+
+    /// The corrected oscillator frequency in MHz, for
+    /// [Control::crystal_frequency].
+    pub fn oscillator_mhz(&self) -> f64 {
+        self.oscillator_hz / 1e6
+    }
+
+    /// The register value for `hz`, with the correction applied.
+    pub fn frequency(&self, hz: f64, ic_version: ICVersion) -> Frequency {
+        Frequency::from_hz(hz, self.oscillator_hz, ic_version)
+    }
+}
+
+/// A VFO that keeps its frequency as an integer register value plus an
+/// integer residual, rather than as a floating-point number of Hz, so that
+/// any number of small relative tuning steps accumulate exactly, and the
+/// displayed and tuned frequencies can never drift apart.
+///
+/// The residual is in units of 1 / 2^20 Hz on SX1255, 1 / 2^19 Hz on SX1257,
+/// which makes the step resolution exactly `crystal_hz` units. The crystal is
+/// an integer number of Hz for that reason; a crystal error below 1 Hz is
+/// far smaller than the step resolution.
+pub struct Vfo {
+    crystal_hz: u64,
+    divisor_bits: u32,
+    register: u32,
+    residual: u64,
+}
+
+impl Vfo {
+    /// A VFO tuned to `hz`, saturating at the ends of the register range,
+    /// for a crystal of `crystal_hz`. None if `crystal_hz` is zero.
+    pub fn new(hz: u64, crystal_hz: u64, ic_version: ICVersion) -> Option<Vfo> {
+        if crystal_hz == 0 {
+            return None;
+        }
+        let divisor_bits = ic_version.frequency_divisor_bits();
+        let mut vfo = Vfo { crystal_hz, divisor_bits, register: 0, residual: 0 };
+        vfo.set_scaled((hz as i128) << divisor_bits);
+        Some(vfo)
+    }
+
+    /// The frequency scaled by 2^divisor_bits.
+    fn scaled(&self) -> i128 {
+        self.register as i128 * self.crystal_hz as i128 + self.residual as i128
+    }
+
+    /// Set the frequency scaled by 2^divisor_bits, saturating at the ends of
+    /// the 24-bit register range.
+    fn set_scaled(&mut self, scaled: i128) {
+        let max = Frequency::MAX as i128 * self.crystal_hz as i128;
+        let scaled = scaled.clamp(0, max);
+        self.register = (scaled / self.crystal_hz as i128) as u32;
+        self.residual = (scaled % self.crystal_hz as i128) as u64;
+    }
+
+    /// Tune up or down by a whole number of Hz.
+    pub fn tune_relative(&mut self, delta_hz: i64) {
+        self.set_scaled(self.scaled() + ((delta_hz as i128) << self.divisor_bits));
+    }
+
+    /// Tune up or down by a whole number of register steps, leaving the
+    /// sub-step residual alone.
+    pub fn nudge_steps(&mut self, steps: i32) {
+        self.set_scaled(self.scaled() + steps as i128 * self.crystal_hz as i128);
+    }
+
+    /// The current frequency in Hz.
+    pub fn current_hz(&self) -> f64 {
+        self.scaled() as f64 / (1u64 << self.divisor_bits) as f64
+    }
+
+    /// The register value, which tunes to at or below
+    /// [current_hz](Self::current_hz).
+    pub fn frequency(&self) -> Frequency {
+        Frequency::from_raw(self.register)
+    }
+
+    /// The sub-step residual in Hz, which the SDR software must shift in
+    /// baseband. Like [Control::offset], it's never negative.
+    pub fn offset_hz(&self) -> f64 {
+        self.residual as f64 / (1u64 << self.divisor_bits) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_frequency_has_no_offset() {
+        let control = Control { crystal_frequency: 36.0, ..Default::default() };
+        let exact = Frequency::from_raw(0xC0E38E).to_hz(36e6, ICVersion::SX1255);
+        assert_eq!(control.offset(exact), 0.0);
+    }
+
+    #[test]
+    fn calibrated_crystal_lowers_the_register_value() {
+        let mut control = Control {
+            crystal_frequency: 36.0,
+            receive: Receive { frequency: 434.0, ..Default::default() },
+            ..Default::default()
+        };
+        let nominal = control.to_hard_registers().rx.raw();
+
+        // A fast crystal has a larger step, so fewer steps reach 434 MHz.
+        control.calibrate_crystal(36.0, 20.0);
+        assert!((control.crystal_frequency - 36.00072).abs() < 1e-9);
+        let calibrated = control.to_hard_registers().rx;
+        assert!(calibrated.raw() < nominal);
+
+        // Where the fast crystal actually tunes is within a step below 434 MHz.
+        let crystal = control.crystal_frequency * 1e6;
+        let tuned = calibrated.to_hz(crystal, ICVersion::SX1255);
+        let step = Frequency::step_hz(crystal, ICVersion::SX1255);
+        assert!(tuned <= 434e6 && 434e6 - tuned < step);
+    }
+
+    #[test]
+    fn halfway_frequency_offset_is_half_a_step() {
+        let control = Control { crystal_frequency: 36.0, ..Default::default() };
+        let step = Frequency::step_hz(36e6, ICVersion::SX1255);
+        let halfway = Frequency::from_raw(0xC0E38E).to_hz(36e6, ICVersion::SX1255) + step / 2.0;
+        let offset = control.offset(halfway);
+        assert!((offset - step / 2.0).abs() < 1e-6);
+        assert!(offset >= 0.0 && offset < step);
+    }
+
+    #[test]
+    fn zero_offset_is_dc_bin() {
+        let control = Control { crystal_frequency: 36.0, ..Default::default() };
+        let exact = Frequency::from_raw(0xC0E38E).to_hz(36e6, ICVersion::SX1255);
+        assert_eq!(control.offset_bin(exact, 1024, 192_000.0), Some(0));
+        assert_eq!(control.offset_bin(exact, 0, 192_000.0), None);
+    }
+
+    #[test]
+    fn low_if_nco_offset_recovers_desired_frequency() {
+        let control = Control { crystal_frequency: 36.0, ..Default::default() };
+        let step = Frequency::step_hz(36e6, ICVersion::SX1255);
+        let (lo, nco) = control.low_if_plan(434_000_000.0, 100_000.0);
+
+        assert!(nco >= 100_000.0 && nco < 100_000.0 + step);
+        let recovered = lo.raw() as f64 * step + nco;
+        assert!((recovered - 434_000_000.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn wrong_crystal_yields_ppm_error() {
+        let register = 0xC0E38E;
+        let actual_crystal = 36e6 * (1.0 + 20e-6);
+        let measured = register as f64 * Frequency::step_hz(actual_crystal, ICVersion::SX1257);
+        let ppm = diagnose_crystal_mismatch(36e6, measured, register, ICVersion::SX1257);
+        assert!((ppm - 20.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn vfo_rejects_a_zero_crystal() {
+        assert!(Vfo::new(434_000_000, 0, ICVersion::SX1255).is_none());
+    }
+
+    #[test]
+    fn vfo_nudges_accumulate_exactly() {
+        let mut vfo = Vfo::new(434_000_000, 36_000_000, ICVersion::SX1255).unwrap();
+        let mut net = 0i64;
+        for i in 0..10_000 {
+            let delta = if i % 3 == 0 { -1 } else { 1 };
+            vfo.tune_relative(delta);
+            net += delta;
+        }
+        let expected = 434_000_000.0 + net as f64;
+        assert_eq!(vfo.current_hz(), expected);
+        assert_eq!(vfo.frequency(), Frequency::from_hz(expected, 36e6, ICVersion::SX1255));
+
+        let before = vfo.offset_hz();
+        vfo.nudge_steps(-3);
+        assert_eq!(vfo.frequency().raw(), Frequency::from_hz(expected, 36e6, ICVersion::SX1255).raw() - 3);
+        assert_eq!(vfo.offset_hz(), before);
+    }
+
+    #[test]
+    fn receive_configuration_compiles_to_registers() {
+        let control = Control {
+            crystal_frequency: 36.0,
+            mode: Mode::Receive,
+            receive: Receive {
+                frequency: 434.0,
+                lna_gain: 0.0,
+                baseband_gain: 0.0,
+                zin: 1,
+                adc_bw: 150,
+                pga_bw: 520.0,
+                pll_bw: 300,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let registers = control.to_hard_registers();
+
+        assert_eq!(
+            registers.mode,
+            hard_registers::Mode { standby_enable: true, rx_enable: true, ..Default::default() }
+        );
+        assert_eq!(
+            registers.rx_frontend,
+            RxFrontend {
+                lna_gain: 1,
+                baseband_gain: 12,
+                zin: RxZIn::I200Ω,
+                adc_bw: RxADCBw::BW100To400KHzSX1255,
+                adc_trim: RxADCTrim::XTal36MHz,
+                pga_bw: RxPGABw::BW500KHz,
+                pll_bw: 3,
+                ..Default::default()
+            }
+        );
+        assert_eq!(registers.rx, Frequency::from_hz(434e6, 36e6, ICVersion::SX1255));
+    }
+
+    #[test]
+    fn registers_decode_to_status_and_receive() {
+        let mut registers = HardRegisters::default_for(ICVersion::SX1255);
+        registers.status.eol = true;
+        registers.status.pll_lock_tx = true;
+        registers.rx_frontend.lna_gain = 3;
+        registers.rx_frontend.baseband_gain = 13;
+        registers.tx_frontend.dac_gain = 2;
+
+        let status = Status::from_hard(&registers.status, ICVersion::SX1255);
+        assert!(status.battery_low && status.transmit_pll_locked);
+        assert!(!status.oscillator_stable && !status.receive_pll_locked);
+
+        let receive = Receive::from_hard_registers(&registers, 36.0, ICVersion::SX1255);
+        assert!((receive.frequency - 434.0).abs() < 1e-4);
+        assert_eq!(receive.lna_gain, -12.0);
+        assert_eq!(receive.baseband_gain, 2.0);
+        assert_eq!(receive.input_impedance, 200);
+        assert_eq!(receive.adc_bw, 400);
+        assert_eq!(receive.pll_bw, 300);
+
+        let transmit = Transmit::from_hard_registers(&registers, 36.0, ICVersion::SX1255);
+        assert_eq!(transmit.dac_gain, -3.0);
+        assert_eq!(transmit.mixer_gain, -9.5);
+    }
+
+    #[test]
+    #[cfg(feature = "transport")]
+    fn agc_change_writes_only_the_gain_register() {
+        use crate::transport::{tests::MockChip, Sx125x};
+
+        let previous = Control {
+            crystal_frequency: 36.0,
+            mode: Mode::Receive,
+            receive: Receive { frequency: 434.0, lna_gain: 0.0, ..Default::default() },
+            ..Default::default()
+        };
+        let mut control = previous.clone();
+        control.receive.lna_gain = -12.0;
+        control.receive.baseband_gain = 4.0;
+
+        let mut bytes = [0u8; 0x1B];
+        previous.to_hard_registers().serialize(&mut bytes, ICVersion::SX1255);
+        let mut radio = Sx125x::new(MockChip::new(bytes), ICVersion::SX1255);
+        control.apply_changes(&previous, &mut radio).unwrap();
+        let writes = radio.release().writes;
+        assert_eq!(writes.len(), 1);
+        assert_eq!(writes[0].0, RegisterAddr::RxGain as u8);
+    }
+
+    #[test]
+    fn minimal_builder() {
+        let control = ControlBuilder::new().mode(Mode::Receive).build().unwrap();
+        assert_eq!(control.crystal_frequency, 36.0);
+        assert_eq!(control.mode, Mode::Receive);
+        assert_eq!(control.receive.lna_gain, 0.0);
+        assert!((control.receive.frequency - 434.0).abs() < 0.01);
+        assert_eq!(control.to_hard_registers().rx_frontend.lna_gain, 1);
+
+        let control = ControlBuilder::new()
+            .ic_version(ICVersion::SX1257)
+            .receive_frequency_mhz(915.0)
+            .transmit_frequency_mhz(915.5)
+            .lna_gain_db(-12.0)
+            .build()
+            .unwrap();
+        assert_eq!(control.receive.frequency, 915.0);
+        assert_eq!(control.transmit.frequency, 915.5);
+    }
+
+    #[test]
+    fn builder_rejects_frequency_outside_the_band() {
+        let error = ControlBuilder::new().receive_frequency_mhz(868.0).build().unwrap_err();
+        assert_eq!(error.register, 0x01);
+        assert_eq!(error.field, "receive.frequency");
+        assert_eq!(error.value, 868_000_000);
+
+        let error = ControlBuilder::new()
+            .ic_version(ICVersion::SX1257)
+            .receive_frequency_mhz(868.0)
+            .transmit_frequency_mhz(434.0)
+            .build()
+            .unwrap_err();
+        assert_eq!(error.field, "transmit.frequency");
+    }
+
+    #[test]
+    fn battery_lower_limit_sets_sx1257_threshold() {
+        let mut control = Control {
+            battery_lower_limit: 2.7,
+            ic_version: ICVersion::SX1257,
+            ..Default::default()
+        };
+        let registers = control.to_hard_registers();
+        assert_eq!(registers.low_battery_threshold.threshold, ThresholdValue::V2_724);
+        control.ic_version = ICVersion::SX1255;
+        let registers = control.to_hard_registers();
+        assert_eq!(registers.low_battery_threshold, LowBatteryThreshold::default());
+    }
+
+    #[test]
+    fn loopback_with_power_amplifier_is_rejected() {
+        for (loop_back, field) in
+            [(LoopBack::RF, "rf_loopback_enable"), (LoopBack::Digital, "dig_loopback_enable")]
+        {
+            let control = Control { mode: Mode::Transmit, loop_back, ..Default::default() };
+            let errors = control.to_hard_registers().validate(ICVersion::SX1255);
+            let errors = errors.unwrap_err();
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].field, field);
+        }
+
+        // Loop-back alone, for calibration in full duplex without the
+        // amplifier, or with the receiver only.
+        let control =
+            Control { mode: Mode::Receive, loop_back: LoopBack::RF, ..Default::default() };
+        let registers = control.to_hard_registers();
+        assert_eq!(registers.validate(ICVersion::SX1255), Ok(()));
+    }
+
+    #[test]
+    fn disciplined_crystal_shifts_the_register() {
+        let mut calibration = CrystalCalibration::new(36e6);
+        let before = calibration.frequency(434e6, ICVersion::SX1255).raw();
+        // The oscillator runs 1 ppm fast, so fewer steps reach 434 MHz:
+        // 434 Hz at 34.33 Hz per step.
+        calibration.discipline(1000.0);
+        assert!((calibration.oscillator_hz - 36_000_036.0).abs() < 1e-6);
+        let after = calibration.frequency(434e6, ICVersion::SX1255).raw();
+        assert!((12..=13).contains(&(before - after)), "{}", before - after);
+        let tuned = Frequency::from_raw(after).to_hz(calibration.oscillator_hz, ICVersion::SX1255);
+        assert!((434e6 - tuned) < Frequency::step_hz(36e6, ICVersion::SX1255));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn control_round_trips_through_toml() {
+        let preset = r#"
+            crystal_frequency = 36.0
+            ic_version = "SX1257"
+            mode = "Receive"
+
+            [receive]
+            frequency = 868.1
+            lna_gain = -12.0
+            pga_bw = 500.0
+        "#;
+        let control: Control = toml::from_str(preset).unwrap();
+        assert_eq!(control.mode, Mode::Receive);
+        assert_eq!(control.ic_version, ICVersion::SX1257);
+        assert_eq!(control.receive.frequency, 868.1);
+        // Unspecified settings take their defaults.
+        assert_eq!(control.loop_back, LoopBack::Off);
+        assert_eq!(control.transmit, Transmit::default());
+
+        let text = toml::to_string(&control).unwrap();
+        assert!(text.contains("mode = \"Receive\""));
+        assert_eq!(toml::from_str::<Control>(&text).unwrap(), control);
+    }
 }
@@ -0,0 +1,520 @@
+// Copyright (C) 2025 Bruce Perens
+// All Rights Reserved
+// This software is not presently under an Open Source license, I'll consider
+// what to do about that if someone pays me to do so, or when I'm done.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use crate::hard_registers::{DigitalBridge, IISMMode, IISM};
+use core::f64::consts::PI;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+/// Generate FIR coefficients equivalent to the SX1255 transmit FIR-DAC, so
+/// that software transmit on an SX1257, or ahead of the SX1255 FIR, matches
+/// the hardware. `count` is the number of taps, 24 + (8 * value) for
+/// [TxFrontend1255::dac_bw](crate::hard_registers::TxFrontend1255::dac_bw),
+/// `bandwidth_khz` is the SSB passband, and `sample_rate_hz` is the rate at
+/// which the filter runs.
+///
+/// This is a Hamming-windowed sinc. The cutoff is placed half a transition
+/// band above `bandwidth_khz`, so that the passband is flat all the way out
+/// to the requested bandwidth. The taps are normalized to unity gain at DC.
+#[cfg(feature = "alloc")]
+pub fn fir_dac_taps(count: u8, bandwidth_khz: f32, sample_rate_hz: f64) -> Vec<f32> {
+    let n = count as usize;
+    // Transition band width of a Hamming window.
+    let transition = 3.3 * sample_rate_hz / n as f64;
+    let cutoff = (bandwidth_khz as f64 * 1e3 + transition / 2.0) / sample_rate_hz;
+    windowed_sinc(n, cutoff)
+}
+
+/// `n` taps of a Hamming-windowed sinc low-pass, normalized to unity gain at
+/// DC. `cutoff` is a fraction of the sample rate.
+#[cfg(feature = "alloc")]
+fn windowed_sinc(n: usize, cutoff: f64) -> Vec<f32> {
+    let middle = (n as f64 - 1.0) / 2.0;
+
+    let taps: Vec<f64> = (0..n)
+        .map(|i| {
+            let t = i as f64 - middle;
+            let sinc = if t == 0.0 {
+                2.0 * cutoff
+            } else {
+                (2.0 * PI * cutoff * t).sin() / (PI * t)
+            };
+            let window = 0.54 - 0.46 * (2.0 * PI * i as f64 / (n as f64 - 1.0)).cos();
+            sinc * window
+        })
+        .collect();
+
+    let sum: f64 = taps.iter().sum();
+    taps.iter().map(|tap| (tap / sum) as f32).collect()
+}
+
+/// A streaming I/Q decimator, for running an SX1255 in Mode A, or an
+/// SX1257, at the lower rate the digital bridge would provide. Build it with
+/// [Decimator::for_bridge] and the same application code works whether or
+/// not the hardware bridge is engaged.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct Decimator {
+    factor: u32,
+    taps: Vec<f32>,
+    history: Vec<(f32, f32)>,
+    position: usize,
+    phase: u32,
+}
+
+#[cfg(feature = "alloc")]
+impl Decimator {
+    /// Taps of the anti-alias filter per unit of decimation.
+    const TAPS_PER_FACTOR: u32 = 16;
+
+    /// Passband edge as a fraction of the output Nyquist frequency.
+    const PASSBAND: f64 = 0.8;
+
+    /// Decimate by `factor`. A factor of 1 passes samples through.
+    pub fn new(factor: u32) -> Decimator {
+        let factor = factor.max(1);
+        let taps = if factor == 1 {
+            alloc::vec![1.0]
+        } else {
+            let cutoff = Self::PASSBAND * 0.5 / factor as f64;
+            windowed_sinc((Self::TAPS_PER_FACTOR * factor) as usize, cutoff)
+        };
+        let history = alloc::vec![(0.0, 0.0); taps.len()];
+        Decimator { factor, taps, history, position: 0, phase: 0 }
+    }
+
+    /// The software decimation that brings the I/Q stream to `total_factor`
+    /// below the sigma-delta rate. In Mode A the whole factor is done here;
+    /// in Modes B1 and B2 the bridge has already done its part, and `None`
+    /// is returned if the remainder isn't a whole number or the bridge
+    /// setting is invalid. On an SX1257 pass a default IISM, which is Mode A.
+    pub fn for_bridge(
+        iism: &IISM,
+        bridge: &DigitalBridge,
+        total_factor: u32,
+    ) -> Option<Decimator> {
+        let hardware = match iism.mode {
+            IISMMode::A => 1,
+            IISMMode::B1 | IISMMode::B2 => bridge.factor()?,
+        };
+        if total_factor == 0 || total_factor % hardware != 0 {
+            return None;
+        }
+        Some(Decimator::new(total_factor / hardware))
+    }
+
+    /// The software decimation factor.
+    pub fn factor(&self) -> u32 {
+        self.factor
+    }
+
+    /// The output sample rate for samples arriving at `input_rate_hz`.
+    pub fn output_rate_hz(&self, input_rate_hz: f64) -> f64 {
+        input_rate_hz / self.factor as f64
+    }
+
+    /// Filter and decimate `input`, appending the results to `output`. The
+    /// filter state carries over between calls, so a stream may be passed
+    /// in pieces of any size.
+    pub fn process(&mut self, input: &[(f32, f32)], output: &mut Vec<(f32, f32)>) {
+        let n = self.history.len();
+        for sample in input {
+            self.history[self.position] = *sample;
+            self.position = (self.position + 1) % n;
+            self.phase += 1;
+            if self.phase == self.factor {
+                self.phase = 0;
+                let (mut i, mut q) = (0.0, 0.0);
+                for (k, tap) in self.taps.iter().enumerate() {
+                    let (si, sq) = self.history[(self.position + n - 1 - k) % n];
+                    i += tap * si;
+                    q += tap * sq;
+                }
+                output.push((i, q));
+            }
+        }
+    }
+}
+
+/// The interpolation stages that make up the digital bridge factor,
+/// mantissa * 3^m * 2^n: n stages of 2, m of 3, and the mantissa, 8 or 9,
+/// last. Their product is [DigitalBridge::factor]. `None` if the setting is
+/// invalid.
+#[cfg(feature = "alloc")]
+pub fn interpolation_stages(bridge: &DigitalBridge) -> Option<Vec<u32>> {
+    let factor = bridge.factor()?;
+    let (m, n) = (bridge.int_dec_m_parameter, bridge.int_dec_n_parameter);
+    let mut stages = alloc::vec![2; n as usize];
+    stages.extend(core::iter::repeat_n(3, m as usize));
+    stages.push((factor >> n) / 3u32.pow(m as u32));
+    Some(stages)
+}
+
+/// A generic linear interpolator with first-order error feedback, for
+/// transmit data: interpolate I/Q samples by a factor, such as the digital
+/// bridge factor, and requantize them to a chosen number of bits, so that
+/// the quantization error is pushed out of the signal band toward the band
+/// edge. It is not a model of the TX Noise Shaper of the data sheet.
+///
+/// Input samples are full scale at ±1.0. Output samples are signed codes of
+/// [bits](Self::bits) bits, ready for [IISMTruncation::to_bus](
+/// crate::hard_registers::IISMTruncation::to_bus).
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct TxNoiseShaper {
+    factor: u32,
+    bits: u8,
+    previous: (f32, f32),
+    error: (f32, f32),
+}
+
+#[cfg(feature = "alloc")]
+impl TxNoiseShaper {
+    /// Interpolate by `factor` and requantize to `bits` bits, 2 to 24.
+    pub fn new(factor: u32, bits: u8) -> TxNoiseShaper {
+        TxNoiseShaper {
+            factor: factor.max(1),
+            bits: bits.clamp(2, 24),
+            previous: (0.0, 0.0),
+            error: (0.0, 0.0),
+        }
+    }
+
+    /// The shaper interpolating by the factor of a digital bridge setting,
+    /// requantizing to `bits` bits chosen by the caller. `None` if the
+    /// setting is invalid.
+    pub fn for_bridge(bridge: &DigitalBridge, bits: u8) -> Option<TxNoiseShaper> {
+        Some(TxNoiseShaper::new(bridge.factor()?, bits))
+    }
+
+    /// The interpolation factor.
+    pub fn factor(&self) -> u32 {
+        self.factor
+    }
+
+    /// The bits per output sample.
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    /// Requantize one value, carrying the error into the next.
+    fn shape(value: f32, error: &mut f32, full_scale: f32) -> i32 {
+        let wanted = value * full_scale + *error;
+        let code = wanted.round().clamp(-full_scale, full_scale - 1.0);
+        *error = wanted - code;
+        code as i32
+    }
+
+    /// Interpolate and requantize `input`, appending [factor](Self::factor)
+    /// output samples per input sample to `output`. Samples are linearly
+    /// interpolated from the previous input, and the state carries over
+    /// between calls, so a stream may be passed in pieces of any size.
+    pub fn process(&mut self, input: &[(f32, f32)], output: &mut Vec<(i32, i32)>) {
+        let full_scale = (1u32 << (self.bits - 1)) as f32;
+        for &(i, q) in input {
+            let (pi, pq) = self.previous;
+            for k in 1..=self.factor {
+                let t = k as f32 / self.factor as f32;
+                output.push((
+                    Self::shape(pi + (i - pi) * t, &mut self.error.0, full_scale),
+                    Self::shape(pq + (q - pq) * t, &mut self.error.1, full_scale),
+                ));
+            }
+            self.previous = (i, q);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// I/Q imbalance corrections measured from a tone, by [estimate_iq_imbalance].
+/// The [Default] is the identity correction.
+pub struct IqCalibration {
+    /// Amplitude of Q relative to I. 1.0 is balanced.
+    pub gain_mismatch: f32,
+    /// Departure of Q from quadrature with I, in radians. 0.0 is balanced.
+    pub phase_imbalance_rad: f32,
+    /// The DC offset of I, in sample units.
+    pub dc_offset_i: f32,
+    /// The DC offset of Q, in sample units.
+    pub dc_offset_q: f32,
+}
+
+impl Default for IqCalibration {
+    fn default() -> Self {
+        IqCalibration {
+            gain_mismatch: 1.0,
+            phase_imbalance_rad: 0.0,
+            dc_offset_i: 0.0,
+            dc_offset_q: 0.0,
+        }
+    }
+}
+
+impl IqCalibration {
+    /// Remove the measured DC offset and imbalance from one sample.
+    pub fn correct(&self, i: f32, q: f32) -> (f32, f32) {
+        let i = i - self.dc_offset_i;
+        let q = (q - self.dc_offset_q) / self.gain_mismatch;
+        let (sin, cos) = self.phase_imbalance_rad.sin_cos();
+        (i, (q - i * sin) / cos)
+    }
+}
+
+/// Measure I/Q imbalance from samples of a single tone that is offset from
+/// DC, such as a transmitted tone received in RF loop-back. The capture
+/// should hold a whole number of cycles of the tone, or many cycles, for
+/// the averages to settle.
+///
+/// With the tone taken as I = cos(wt) and Q = g sin(wt + φ), the DC offsets
+/// are the means, g is the ratio of the RMS amplitudes, and sin φ is the
+/// correlation of I and Q normalized by their powers. `None` if I or Q
+/// holds no signal, since then there's no imbalance to measure.
+pub fn estimate_iq_imbalance(samples: &[(f32, f32)]) -> Option<IqCalibration> {
+    if samples.is_empty() {
+        return None;
+    }
+    let n = samples.len() as f64;
+    let dc_i = samples.iter().map(|(i, _)| *i as f64).sum::<f64>() / n;
+    let dc_q = samples.iter().map(|(_, q)| *q as f64).sum::<f64>() / n;
+    let (mut power_i, mut power_q, mut correlation) = (0.0, 0.0, 0.0);
+    for (i, q) in samples {
+        let i = *i as f64 - dc_i;
+        let q = *q as f64 - dc_q;
+        power_i += i * i;
+        power_q += q * q;
+        correlation += i * q;
+    }
+    if power_i == 0.0 || power_q == 0.0 {
+        return None;
+    }
+    Some(IqCalibration {
+        gain_mismatch: (power_q / power_i).sqrt() as f32,
+        phase_imbalance_rad: (correlation / (power_i * power_q).sqrt()).asin() as f32,
+        dc_offset_i: dc_i as f32,
+        dc_offset_q: dc_q as f32,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How [SpectrumScan::accumulate] combines the sweeps at each frequency.
+pub enum Accumulation {
+    /// The highest level seen in any sweep.
+    MaxHold,
+    /// The mean power over all sweeps. The levels are averaged as power,
+    /// not as dB.
+    Average,
+}
+
+/// The levels measured over repeated sweeps of a set of frequencies, for a
+/// waterfall or a spectrum display. The levels are in dB, in whatever
+/// reference the caller measures them, usually dBm or dBFS from the
+/// receive samples.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpectrumScan {
+    frequencies_hz: Vec<f64>,
+    sweeps: Vec<Vec<f32>>,
+}
+
+#[cfg(feature = "alloc")]
+impl SpectrumScan {
+    /// Start a scan of the frequency bins `frequencies_hz`, with no sweeps.
+    pub fn new(frequencies_hz: Vec<f64>) -> SpectrumScan {
+        SpectrumScan { frequencies_hz, sweeps: Vec::new() }
+    }
+
+    /// The frequency of each bin, in Hz.
+    pub fn frequencies_hz(&self) -> &[f64] {
+        &self.frequencies_hz
+    }
+
+    /// The number of sweeps added so far.
+    pub fn sweep_count(&self) -> usize {
+        self.sweeps.len()
+    }
+
+    /// Add one sweep, holding a level for each frequency bin.
+    ///
+    /// # Panics
+    /// If `levels_db` doesn't hold one level per frequency bin.
+    pub fn add_sweep(&mut self, levels_db: &[f32]) {
+        assert_eq!(levels_db.len(), self.frequencies_hz.len(), "one level per frequency bin");
+        self.sweeps.push(levels_db.to_vec());
+    }
+
+    /// The (time, frequency) matrix for a waterfall: one row per sweep, in
+    /// the order they were added, with one column per frequency bin.
+    pub fn waterfall(&self) -> &[Vec<f32>] {
+        &self.sweeps
+    }
+
+    /// Combine all of the sweeps into one level per frequency bin. With no
+    /// sweeps, every bin is negative infinity.
+    pub fn accumulate(&self, accumulation: Accumulation) -> Vec<f32> {
+        (0..self.frequencies_hz.len())
+            .map(|bin| {
+                let levels = self.sweeps.iter().map(|sweep| sweep[bin]);
+                match accumulation {
+                    Accumulation::MaxHold => levels.fold(f32::NEG_INFINITY, f32::max),
+                    Accumulation::Average => {
+                        if self.sweeps.is_empty() {
+                            return f32::NEG_INFINITY;
+                        }
+                        let power: f64 = levels.map(|db| 10f64.powf(db as f64 / 10.0)).sum();
+                        (10.0 * (power / self.sweeps.len() as f64).log10()) as f32
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Magnitude response in dB of `taps` at `frequency`.
+    fn response_db(taps: &[f32], frequency: f64, sample_rate: f64) -> f64 {
+        let (mut re, mut im) = (0.0, 0.0);
+        for (k, tap) in taps.iter().enumerate() {
+            let w = 2.0 * PI * frequency / sample_rate * k as f64;
+            re += *tap as f64 * w.cos();
+            im -= *tap as f64 * w.sin();
+        }
+        20.0 * (re * re + im * im).sqrt().log10()
+    }
+
+    #[test]
+    fn fir_dac_passband_is_flat_to_bandwidth() {
+        let taps = fir_dac_taps(64, 200.0, 2e6);
+        assert_eq!(taps.len(), 64);
+        for i in 0..=20 {
+            let frequency = 200e3 * i as f64 / 20.0;
+            assert!(response_db(&taps, frequency, 2e6).abs() < 0.5);
+        }
+        // Well outside the passband, it's a filter.
+        assert!(response_db(&taps, 500e3, 2e6) < -40.0);
+    }
+
+    #[test]
+    fn spectrum_scan_accumulates_sweeps() {
+        let mut scan = SpectrumScan::new(vec![433.0e6, 433.1e6, 433.2e6]);
+        // A synthetic carrier in the middle bin, over a -100 dB floor.
+        let sweep = [-100.0, -40.0, -100.0];
+        scan.add_sweep(&sweep);
+        scan.add_sweep(&sweep);
+        assert_eq!(scan.sweep_count(), 2);
+        assert_eq!(scan.waterfall(), &[sweep.to_vec(), sweep.to_vec()]);
+
+        for (level, expected) in scan.accumulate(Accumulation::Average).iter().zip(sweep) {
+            assert!((level - expected).abs() < 1e-3);
+        }
+
+        // Averaged as power: half of the power of -40 dB is 3 dB down.
+        scan.add_sweep(&[-100.0, -200.0, -100.0]);
+        scan.add_sweep(&[-100.0, -200.0, -100.0]);
+        let average = scan.accumulate(Accumulation::Average);
+        assert!((average[1] - -43.01).abs() < 0.01);
+        assert_eq!(scan.accumulate(Accumulation::MaxHold), vec![-100.0, -40.0, -100.0]);
+    }
+
+    /// A tone of `cycles` over `n` samples with the given imbalance.
+    pub(crate) fn imbalanced_tone(
+        n: usize,
+        cycles: f64,
+        gain: f64,
+        phase: f64,
+        dc: (f64, f64),
+    ) -> Vec<(f32, f32)> {
+        (0..n)
+            .map(|k| {
+                let w = 2.0 * PI * cycles * k as f64 / n as f64;
+                ((w.cos() + dc.0) as f32, (gain * (w + phase).sin() + dc.1) as f32)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn iq_imbalance_is_measured_and_corrected() {
+        let samples = imbalanced_tone(1024, 37.0, 1.1, 0.05, (0.02, -0.03));
+        let calibration = estimate_iq_imbalance(&samples).unwrap();
+        assert!((calibration.gain_mismatch - 1.1).abs() < 1e-4);
+        assert!((calibration.phase_imbalance_rad - 0.05).abs() < 1e-4);
+        assert!((calibration.dc_offset_i - 0.02).abs() < 1e-4);
+        assert!((calibration.dc_offset_q - -0.03).abs() < 1e-4);
+
+        let corrected: Vec<(f32, f32)> =
+            samples.iter().map(|(i, q)| calibration.correct(*i, *q)).collect();
+        let residual = estimate_iq_imbalance(&corrected).unwrap();
+        assert!((residual.gain_mismatch - 1.0).abs() < 1e-4);
+        assert!(residual.phase_imbalance_rad.abs() < 1e-4);
+    }
+
+    #[test]
+    fn iq_imbalance_needs_signal_on_both_channels() {
+        assert_eq!(estimate_iq_imbalance(&[]), None);
+        let i_only: Vec<(f32, f32)> = (0..64).map(|n| ((n as f32).cos(), 0.5)).collect();
+        assert_eq!(estimate_iq_imbalance(&i_only), None);
+        assert_eq!(IqCalibration::default().correct(0.25, -0.5), (0.25, -0.5));
+    }
+
+    #[test]
+    fn mode_a_decimates_to_the_bridge_rate() {
+        let bridge = DigitalBridge::from_factor(8).unwrap();
+        let mode_a = IISM::default();
+        let mode_b = IISM { mode: IISMMode::B1, ..Default::default() };
+
+        let software = Decimator::for_bridge(&mode_a, &bridge, 8).unwrap();
+        let hardware = Decimator::for_bridge(&mode_b, &bridge, 8).unwrap();
+        assert_eq!(software.factor(), 8);
+        assert_eq!(hardware.factor(), 1);
+
+        // Mode A delivers samples at the sigma-delta rate, Mode B has already
+        // divided it by the bridge factor.
+        let rate = 1e6;
+        let bridge_rate = rate / bridge.factor().unwrap() as f64;
+        assert_eq!(software.output_rate_hz(rate), hardware.output_rate_hz(bridge_rate));
+
+        // A DC stream passes at unity gain, and comes out at an eighth the
+        // number of samples, however it is split.
+        let mut software = software;
+        let mut output = Vec::new();
+        software.process(&[(0.5, -0.25); 300], &mut output);
+        software.process(&[(0.5, -0.25); 500], &mut output);
+        assert_eq!(output.len(), 100);
+        let (i, q) = output[99];
+        assert!((i - 0.5).abs() < 1e-4 && (q - -0.25).abs() < 1e-4);
+
+        assert!(Decimator::for_bridge(&mode_b, &bridge, 12).is_none());
+        assert_eq!(Decimator::for_bridge(&mode_b, &bridge, 16).unwrap().factor(), 2);
+    }
+
+    #[test]
+    fn noise_shaper_interpolates_by_the_bridge_factor() {
+        let bridge = DigitalBridge::from_factor(72).unwrap();
+        let stages = interpolation_stages(&bridge).unwrap();
+        assert_eq!(stages, [2, 2, 2, 9]);
+        assert_eq!(stages.iter().product::<u32>(), 72);
+
+        let mut shaper = TxNoiseShaper::for_bridge(&bridge, 12).unwrap();
+        assert_eq!(shaper.factor(), 72);
+        assert_eq!(shaper.bits(), 12);
+
+        let mut output = Vec::new();
+        shaper.process(&[(0.25, -0.5); 3], &mut output);
+        shaper.process(&[(0.25, -0.5); 2], &mut output);
+        assert_eq!(output.len(), 5 * 72);
+
+        // Once the interpolation has settled on a constant input, the noise
+        // shaped codes average to the input.
+        let full_scale = (1 << (shaper.bits() - 1)) as f32;
+        let settled = &output[72..];
+        let mean = settled.iter().map(|(i, _)| *i as f32).sum::<f32>() / settled.len() as f32;
+        assert!((mean / full_scale - 0.25).abs() < 0.01);
+    }
+}
@@ -0,0 +1,122 @@
+// Copyright (C) 2025 Bruce Perens
+// All Rights Reserved
+// This software is not presently under an Open Source license, I'll consider
+// what to do about that if someone pays me to do so, or when I'm done.
+
+//! Errors reported by this crate.
+
+use crate::hard_registers::{RegisterAddr, ValidationError};
+use core::convert::Infallible;
+use core::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The error type used throughout this crate. `E` is the error of the SPI
+/// bus, for the transport. Everything else uses the default, which has no
+/// bus errors.
+pub enum Error<E = Infallible> {
+    /// A setting can't be represented by the IC, or conflicts with another
+    /// setting. The name of the offending field is included.
+    InvalidRegisterValue(&'static str),
+
+    /// A line of a logic analyzer capture couldn't be parsed. The line
+    /// number, counting from 1, is included.
+    InvalidCsv(usize),
+
+    /// The IC didn't report ready within the time allowed.
+    Timeout,
+
+    /// The transmit gains would drive the output above the allowed power.
+    PowerLimitExceeded,
+
+    /// A frequency is outside of the RF band of the IC, or can't be reached
+    /// with the 24-bit frequency register.
+    FrequencyOutOfRange,
+
+    /// The register or feature isn't documented for this IC, such as the
+    /// I²S interface on the SX1257. The name of the register is included.
+    UnsupportedOnIcVersion(&'static str),
+
+    /// A measurement found no signal to work with, such as an I/Q
+    /// calibration capture where I or Q is silent.
+    NoSignal,
+
+    /// The SPI bus reported an error, which is included as the HAL gave it.
+    Spi(E),
+}
+
+impl Error {
+    /// The same error, as reported by a transport whose bus errors are `E`.
+    pub fn into_bus_error<E>(self) -> Error<E> {
+        match self {
+            Error::InvalidRegisterValue(field) => Error::InvalidRegisterValue(field),
+            Error::InvalidCsv(line) => Error::InvalidCsv(line),
+            Error::Timeout => Error::Timeout,
+            Error::PowerLimitExceeded => Error::PowerLimitExceeded,
+            Error::FrequencyOutOfRange => Error::FrequencyOutOfRange,
+            Error::UnsupportedOnIcVersion(register) => Error::UnsupportedOnIcVersion(register),
+            Error::NoSignal => Error::NoSignal,
+            Error::Spi(never) => match never {},
+        }
+    }
+}
+
+impl<E: fmt::Debug> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidRegisterValue(field) => write!(f, "invalid value for {}", field),
+            Error::InvalidCsv(line) => write!(f, "can't parse line {} of the SPI capture", line),
+            Error::Timeout => write!(f, "the IC didn't become ready in time"),
+            Error::PowerLimitExceeded => write!(f, "transmit power would exceed the limit"),
+            Error::FrequencyOutOfRange => write!(f, "frequency is out of the range of the IC"),
+            Error::UnsupportedOnIcVersion(register) => {
+                write!(f, "{} isn't documented for this IC", register)
+            }
+            Error::NoSignal => write!(f, "no signal to measure"),
+            Error::Spi(error) => write!(f, "SPI error: {:?}", error),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: fmt::Debug> std::error::Error for Error<E> {}
+
+/// A frequency that fails validation is out of range, anything else is an
+/// invalid value in the named field.
+impl<E> From<ValidationError> for Error<E> {
+    fn from(error: ValidationError) -> Self {
+        match RegisterAddr::from_addr(error.register) {
+            Some(RegisterAddr::RxFreqMsb | RegisterAddr::TxFreqMsb) => Error::FrequencyOutOfRange,
+            _ => Error::InvalidRegisterValue(error.field),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control::ControlBuilder;
+
+    #[test]
+    fn display_names_the_field() {
+        let error: Error = Error::InvalidRegisterValue("lna_gain");
+        assert_eq!(format!("{}", error), "invalid value for lna_gain");
+        let error: Error = Error::UnsupportedOnIcVersion("IISM");
+        assert_eq!(format!("{}", error), "IISM isn't documented for this IC");
+    }
+
+    #[test]
+    fn validation_errors_convert() {
+        let error = ControlBuilder::new().receive_frequency_mhz(100.0).build().unwrap_err();
+        assert_eq!(<Error>::from(error), Error::FrequencyOutOfRange);
+        let error = ValidationError { register: 0x0C, field: "lna_gain", value: 0 };
+        assert_eq!(<Error>::from(error), Error::InvalidRegisterValue("lna_gain"));
+    }
+
+    #[test]
+    fn bus_errors_keep_the_hal_error() {
+        let error: Error<&str> = Error::Timeout.into_bus_error();
+        assert_eq!(error, Error::Timeout);
+        let error: Error<&str> = Error::Spi("overrun");
+        assert_eq!(format!("{}", error), "SPI error: \"overrun\"");
+    }
+}
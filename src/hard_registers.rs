@@ -4,8 +4,15 @@
 // what to do about that if someone pays me to do so, or when I'm done.
 
 use binary_serde::*;
-
-#[derive(Debug, Default, PartialEq, Eq)]
+use core::ops::RangeInclusive;
+use crate::Error;
+#[cfg(feature = "alloc")]
+use alloc::{collections::BTreeMap, format, string::String, vec::Vec};
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+#[derive(Debug, Clone, Hash, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[binary_serde_bitfield(order = BitfieldBitOrder::MsbFirst)]
 /// # Operating modes of the IC.
 ///
@@ -46,7 +53,8 @@ pub struct Mode {
     pub standby_enable: bool,
 }
 
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[binary_serde_bitfield(order = BitfieldBitOrder::MsbFirst)]
 /// Integer frequency value.
 /// To calculate the frequency, first find the step resolution.
@@ -68,7 +76,7 @@ pub struct Mode {
 /// 0xC0E38E is the default value of the hardware register, and should 
 /// result in 434 MHz on SX1255 with a 36 MHz crystal, 868 on SX1257.
 /// The step resolution will be 34.3323 Hz on SX1255 if the oscillator is 36 MHz,
-/// 38.6646 on SX1257.
+/// 68.6646 on SX1257.
 /// This value is read only when the least significant byte is written to
 /// the IC, OR when the IC enters STANDBY mode from SLEEP mode by a
 /// transition of
@@ -82,7 +90,121 @@ pub struct Frequency {
   frequency: u32,
 }
 
-#[derive(Debug, Default, PartialEq, Eq)]
+impl Frequency {
+    /// The largest value that fits in the 24-bit hardware register.
+    pub const MAX: u32 = 0x00FF_FFFF;
+
+    /// Create a frequency from a raw register value. Only the low 24 bits
+    /// are used, the upper 8 bits of the [u32](core::primitive::u32) are
+    /// masked off so that they can never be serialized into the neighboring
+    /// register.
+    pub const fn from_raw(raw: u32) -> Frequency {
+        Frequency { frequency: raw & Self::MAX }
+    }
+
+    /// Create a frequency from a raw register value, or `None` if it
+    /// doesn't fit in the 24-bit register. See [from_raw](Self::from_raw)
+    /// to mask instead.
+    pub const fn new(raw: u32) -> Option<Frequency> {
+        if raw > Self::MAX {
+            None
+        } else {
+            Some(Frequency { frequency: raw })
+        }
+    }
+
+    /// The raw 24-bit register value.
+    pub const fn raw(&self) -> u32 {
+        self.frequency
+    }
+
+    /// The step resolution in Hz for the given oscillator frequency in Hz.
+    pub fn step_hz(oscillator_frequency: f64, ic_version: ICVersion) -> f64 {
+        oscillator_frequency / (1u32 << ic_version.frequency_divisor_bits()) as f64
+    }
+
+    /// The register value for `hz`, given the oscillator frequency in Hz.
+    /// This picks the largest value whose frequency is lower-than or equal-to
+    /// the request, so that the residual reported by
+    /// [Control::offset](crate::control::Control::offset) is never negative.
+    /// Requests beyond the 24-bit range saturate rather than wrapping.
+    pub fn from_hz(hz: f64, crystal_frequency: f64, ic_version: ICVersion) -> Frequency {
+        let step = Self::step_hz(crystal_frequency, ic_version);
+        let value = (hz / step).floor().clamp(0.0, Self::MAX as f64);
+        Frequency::from_raw(value as u32)
+    }
+
+    /// The register value for `hz`, as [from_hz](Self::from_hz) chooses it,
+    /// or [Error::FrequencyOutOfRange] if `hz` is outside of the RF band of
+    /// the IC, or at or above the 24-bit ceiling of 2^24 steps for this
+    /// crystal. With a 36 MHz crystal the band is the limit on both ICs:
+    /// SX1255 tunes 400 to 512 MHz, its ceiling being 576 MHz, and SX1257
+    /// 860 to 1000 MHz, its ceiling being 1152 MHz. With a 32 MHz crystal the
+    /// SX1255 ceiling is 512 MHz, just short of the top of the band.
+    pub fn checked_from_hz(
+        hz: f64,
+        crystal_frequency: f64,
+        ic_version: ICVersion,
+    ) -> Result<Frequency, Error> {
+        let (min, _, step) = Self::tuning_range(crystal_frequency, ic_version);
+        let (_, high) = ic_version.rf_range_hz();
+        let ceiling = (Self::MAX as f64 + 1.0) * step;
+        if hz < min || hz > high || hz >= ceiling {
+            return Err(Error::FrequencyOutOfRange);
+        }
+        Ok(Self::from_hz(hz, crystal_frequency, ic_version))
+    }
+
+    /// The documented hop time in seconds for a frequency step of
+    /// `step_hz`: 20µs for steps up to 400 KHz, 30µs for 1.2 MHz, 50µs for
+    /// 25 MHz. Steps in between are interpolated linearly, larger steps are
+    /// taken to be 50µs.
+    pub fn hop_time_s(step_hz: f64) -> f64 {
+        const POINTS: [(f64, f64); 3] = [(400e3, 20e-6), (1.2e6, 30e-6), (25e6, 50e-6)];
+        let step = step_hz.abs();
+        if step <= POINTS[0].0 {
+            return POINTS[0].1;
+        }
+        for pair in POINTS.windows(2) {
+            let ((f0, t0), (f1, t1)) = (pair[0], pair[1]);
+            if step <= f1 {
+                return t0 + (t1 - t0) * (step - f0) / (f1 - f0);
+            }
+        }
+        POINTS[2].1
+    }
+
+    /// The frequency in Hz that this register value tunes to, given the
+    /// oscillator frequency in Hz.
+    pub fn to_hz(&self, crystal_frequency: f64, ic_version: ICVersion) -> f64 {
+        self.frequency as f64 * Self::step_hz(crystal_frequency, ic_version)
+    }
+
+    /// The achievable tuning range for an oscillator frequency in Hz, as
+    /// `(min_hz, max_hz, step_hz)`. The range is the specified RF band of the
+    /// IC, which is where its VCO operates, further limited by the largest
+    /// 24-bit register value, and rounded inward to whole steps.
+    pub fn tuning_range(oscillator_frequency: f64, ic_version: ICVersion) -> (f64, f64, f64) {
+        let step = Self::step_hz(oscillator_frequency, ic_version);
+        let (low, high) = ic_version.rf_range_hz();
+        let high = high.min(Self::MAX as f64 * step);
+        ((low / step).ceil() * step, (high / step).floor() * step, step)
+    }
+}
+
+/// The fastest sustainable frequency-hopping rate, in hops per second, for
+/// hops averaging `avg_step_hz`. Each hop costs the documented settling
+/// time, see [Frequency::hop_time_s], plus writing the three frequency
+/// bytes as single-register SPI accesses of 16 bits each, at
+/// `spi_clock_hz`. Bus turnaround and software overhead aren't counted, so
+/// treat this as an upper bound.
+pub fn max_hop_rate_hz(avg_step_hz: f64, spi_clock_hz: f64) -> f64 {
+    let spi_time = 3.0 * 16.0 / spi_clock_hz;
+    1.0 / (Frequency::hop_time_s(avg_step_hz) + spi_time)
+}
+
+#[derive(Debug, Clone, Hash, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[binary_serde_bitfield(order = BitfieldBitOrder::MsbFirst)]
 /// IC version data. This feature is not documented for SX1257.
 pub struct Version {
@@ -95,7 +217,30 @@ pub struct Version {
     metal_mask_revision_number: u8,
 }
 
-#[derive(Debug, Default, PartialEq, Eq)]
+impl Version {
+    /// Decode the raw value of the version register.
+    pub fn from_raw(raw: u8) -> Version {
+        Version::binary_deserialize(&[raw], Endianness::Big).expect("every byte decodes")
+    }
+
+    /// Identify the IC from its version register. 0x1A, fill revision 1 and
+    /// metal mask revision 0xA, is the SX1255.
+    ///
+    /// The SX1257 data sheet doesn't document this register, so any other
+    /// value is taken to be an SX1257. That is a guess, and an SX1255 with a
+    /// later revision would be misidentified. 0x00 and 0xFF are what a bus
+    /// with no IC answering reads, and give `None`.
+    pub fn detect_ic(&self) -> Option<ICVersion> {
+        match (self.fill_revision_number, self.metal_mask_revision_number) {
+            (0x1, 0xA) => Some(ICVersion::SX1255),
+            (0x0, 0x0) | (0xF, 0xF) => None,
+            _ => Some(ICVersion::SX1257),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Hash, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[binary_serde_bitfield(order = BitfieldBitOrder::MsbFirst)]
 /// Transmit front-end control register.
 pub struct TxFrontend {
@@ -109,13 +254,156 @@ pub struct TxFrontend {
     pub dac_gain: u8,
 
     #[bits(4)]
-    /// Transmit mixer gain. 37.5 + (2 * value) dB. 2 dB steps.
+    /// Transmit mixer gain. -37.5 + (2 * value) dB. 2 dB steps.
     pub mixer_gain: u8,
 
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A set of transmit analog gains, in dB, applied together by
+/// [TxFrontend::set_gains].
+pub struct TxGainSet {
+    /// DAC gain, -9 to 0 dB in 3 dB steps.
+    pub dac_db: f32,
+    /// Mixer gain, -37.5 to -7.5 dB in 2 dB steps.
+    pub mixer_db: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A measurement of transmit output power at one gain setting, for
+/// [TxFrontend::assert_within_power_limit]. The output is taken to follow the
+/// DAC and mixer gains dB for dB from there, so measure with the power
+/// amplifier and antenna path that will be used.
+pub struct TxPowerCalibration {
+    /// The measured output power in dBm.
+    pub measured_dbm: f32,
+    /// The DAC plus mixer gain, in dB, at which it was measured.
+    pub gain_db: f32,
+}
+
+impl TxPowerCalibration {
+    /// The output power in dBm that `tx` would produce.
+    pub fn output_dbm(&self, tx: &TxFrontend) -> f32 {
+        let gain = TxFrontend::dac_gain_from_code(tx.dac_gain)
+            + TxFrontend::mixer_gain_from_code(tx.mixer_gain);
+        self.measured_dbm + gain - self.gain_db
+    }
+}
+
+impl TxFrontend {
+    /// DAC gain in dB for code 0, it increases 3 dB per step.
+    const DAC_GAIN_MIN_DB: f32 = -9.0;
+    const DAC_GAIN_MAX_CODE: u8 = 3;
+
+    /// The test Vref bit of [dac_gain](Self::dac_gain).
+    const DAC_TEST_VREF: u8 = 0x04;
+
+    /// Mixer gain in dB for code 0, it increases 2 dB per step.
+    const MIXER_GAIN_MIN_DB: f32 = -37.5;
+    const MIXER_GAIN_MAX_CODE: u8 = 15;
+
+    /// The DAC gain code nearest to `db`, clamped to the legal range. This
+    /// never sets the test Vref bit.
+    pub(crate) fn dac_gain_code(db: f32) -> u8 {
+        ((db - Self::DAC_GAIN_MIN_DB) / 3.0)
+            .round()
+            .clamp(0.0, Self::DAC_GAIN_MAX_CODE as f32) as u8
+    }
+
+    /// The DAC gain in dB for `code`. The test Vref bit is ignored.
+    pub(crate) fn dac_gain_from_code(code: u8) -> f32 {
+        Self::DAC_GAIN_MIN_DB + 3.0 * (code & Self::DAC_GAIN_MAX_CODE) as f32
+    }
+
+    fn dac_gain_in_range(db: f32) -> bool {
+        (Self::DAC_GAIN_MIN_DB..=0.0).contains(&db)
+    }
+
+    /// Check, before the power amplifier is keyed, that these gains don't
+    /// put the output above `max_dbm`, such as the limit of a license.
+    pub fn assert_within_power_limit(
+        &self,
+        max_dbm: f32,
+        cal: &TxPowerCalibration,
+    ) -> Result<(), Error> {
+        if cal.output_dbm(self) > max_dbm {
+            return Err(Error::PowerLimitExceeded);
+        }
+        Ok(())
+    }
+
+    /// The mixer gain code nearest to `db`, clamped to the legal range.
+    pub(crate) fn mixer_gain_code(db: f32) -> u8 {
+        ((db - Self::MIXER_GAIN_MIN_DB) / 2.0)
+            .round()
+            .clamp(0.0, Self::MIXER_GAIN_MAX_CODE as f32) as u8
+    }
+
+    /// The mixer gain in dB for `code`.
+    pub(crate) fn mixer_gain_from_code(code: u8) -> f32 {
+        Self::MIXER_GAIN_MIN_DB + 2.0 * code.min(Self::MIXER_GAIN_MAX_CODE) as f32
+    }
+
+    fn mixer_gain_in_range(db: f32) -> bool {
+        let max = Self::MIXER_GAIN_MIN_DB + 2.0 * Self::MIXER_GAIN_MAX_CODE as f32;
+        (Self::MIXER_GAIN_MIN_DB..=max).contains(&db)
+    }
+
+    /// Set [dac_gain](Self::dac_gain) to the 3 dB step nearest to `db`,
+    /// clamped to -9 to 0 dB. Like [set_gains](Self::set_gains), this clears
+    /// the test Vref bit; only
+    /// [enable_dac_test_vref](Self::enable_dac_test_vref) sets it.
+    pub fn set_dac_gain_db(&mut self, db: f32) {
+        self.dac_gain = Self::dac_gain_code(db);
+    }
+
+    /// The DAC gain in dB. The test Vref bit is ignored.
+    pub fn dac_gain_db(&self) -> f32 {
+        Self::dac_gain_from_code(self.dac_gain)
+    }
+
+    /// Impose the test Vref voltage on the DAC, by setting the high bit of
+    /// [dac_gain](Self::dac_gain), or remove it. The data sheet doesn't
+    /// describe the test mode further, so leave it off in normal use.
+    pub fn enable_dac_test_vref(&mut self, on: bool) {
+        if on {
+            self.dac_gain |= Self::DAC_TEST_VREF;
+        } else {
+            self.dac_gain &= !Self::DAC_TEST_VREF;
+        }
+    }
+
+    /// Set [mixer_gain](Self::mixer_gain) to the 2 dB step nearest to `db`,
+    /// clamped to -37.5 to -7.5 dB.
+    pub fn set_mixer_gain_db(&mut self, db: f32) {
+        self.mixer_gain = Self::mixer_gain_code(db);
+    }
+
+    /// The mixer gain in dB.
+    pub fn mixer_gain_db(&self) -> f32 {
+        Self::mixer_gain_from_code(self.mixer_gain)
+    }
+
+    /// Set the DAC and mixer gains together. Both values are checked before
+    /// either is changed, so an error leaves the register untouched. Values
+    /// between steps are rounded to the nearest step. The DAC test Vref bit
+    /// is cleared.
+    pub fn set_gains(&mut self, gains: TxGainSet) -> Result<(), Error> {
+        if !Self::dac_gain_in_range(gains.dac_db) {
+            return Err(Error::InvalidRegisterValue("dac_gain"));
+        }
+        if !Self::mixer_gain_in_range(gains.mixer_db) {
+            return Err(Error::InvalidRegisterValue("mixer_gain"));
+        }
+        self.dac_gain = Self::dac_gain_code(gains.dac_db);
+        self.mixer_gain = Self::mixer_gain_code(gains.mixer_db);
+        Ok(())
+    }
+}
+
 #[repr(u8)]
-#[derive(Debug, BinarySerde, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, BinarySerde, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// Settings for [TxFrontend1255::mixer_tank_resistance]
 /// This feature is documented only for SX1255.
 pub enum TxMixerTankResistance {
@@ -130,7 +418,46 @@ pub enum TxMixerTankResistance {
     Ω64000 = 7 // Resistance "off", approximate value.
 }
 
-#[derive(Debug, Default, PartialEq, Eq)]
+impl TxMixerTankResistance {
+    /// Every setting, in register code order, which is ascending resistance.
+    const ALL: [TxMixerTankResistance; 8] = [
+        TxMixerTankResistance::Ω950,
+        TxMixerTankResistance::Ω1110,
+        TxMixerTankResistance::Ω1320,
+        TxMixerTankResistance::Ω1650,
+        TxMixerTankResistance::Ω2180,
+        TxMixerTankResistance::Ω3240,
+        TxMixerTankResistance::Ω6000,
+        TxMixerTankResistance::Ω64000,
+    ];
+
+    /// The resistance in ohms. Code 7 switches the resistor off, and 64000
+    /// is only an approximation of the tank's resistance in that case.
+    pub fn ohms(&self) -> u32 {
+        match self {
+            TxMixerTankResistance::Ω950 => 950,
+            TxMixerTankResistance::Ω1110 => 1110,
+            TxMixerTankResistance::Ω1320 => 1320,
+            TxMixerTankResistance::Ω1650 => 1650,
+            TxMixerTankResistance::Ω2180 => 2180,
+            TxMixerTankResistance::Ω3240 => 3240,
+            TxMixerTankResistance::Ω6000 => 6000,
+            TxMixerTankResistance::Ω64000 => 64000,
+        }
+    }
+
+    /// The setting nearest to `ohms`. Anything nearer to 64000 than to 6000
+    /// gets the "off" setting.
+    pub fn nearest(ohms: u32) -> TxMixerTankResistance {
+        Self::ALL
+            .into_iter()
+            .min_by_key(|resistance| resistance.ohms().abs_diff(ohms))
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Hash, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[binary_serde_bitfield(order = BitfieldBitOrder::MsbFirst)]
 /// Hardware transmit front-end control items documented only for SX1255.
 pub struct TxFrontend1255 {
@@ -158,7 +485,7 @@ pub struct TxFrontend1255 {
     /// The transmit I/Q filters remove quantization noise created by the
     /// transmit I/Q FIR DACs.
     /// Transmit analog filter 3 db DSB bandwidth in MHz = 
-    /// 17.15 * (41 - value). This value has 30% accuracy.
+    /// 17.15 / (41 - value). This value has 30% accuracy.
     /// The filter bandwidth should be set for wider than the transmit
     /// bandwidth to reduce group-delay issues.
     pub filter_bw: u8,
@@ -178,8 +505,116 @@ pub struct TxFrontend1255 {
     pub dac_bw: u8
 }
 
+impl TxFrontend1255 {
+    /// Margin by which each filter must exceed the bandwidth it protects.
+    const BANDWIDTH_MARGIN: f32 = 1.1;
+
+    /// Accuracy of the analog filter bandwidth, from the data sheet.
+    const FILTER_ACCURACY: f32 = 0.3;
+
+    /// Highest [filter_bw](Self::filter_bw) code, the widest analog filter.
+    const FILTER_BW_MAX: u8 = 31;
+
+    /// Highest [dac_bw](Self::dac_bw) code that still adds taps, for 64.
+    const DAC_BW_MAX: u8 = 5;
+
+    /// Analog filter 3 dB DSB bandwidth in MHz, 17.15 / (41 - value).
+    pub fn filter_bw_mhz(&self) -> f32 {
+        17.15 / (41.0 - self.filter_bw.min(Self::FILTER_BW_MAX) as f32)
+    }
+
+    /// Set [filter_bw](Self::filter_bw) for an analog filter DSB bandwidth of
+    /// at least `mhz`, 0.418 to 1.715 MHz. Since the filter has only 30%
+    /// accuracy and should be wider than the signal, a bandwidth between
+    /// steps is rounded to the wider one, which is the higher code.
+    pub fn set_filter_bw_mhz(&mut self, mhz: f32) {
+        // The small allowance keeps an exact step from rounding up a step.
+        let value = 41.0 - 17.15 / mhz - 1e-4;
+        self.filter_bw = value.ceil().clamp(0.0, Self::FILTER_BW_MAX as f32) as u8;
+    }
+
+    /// Number of taps of the transmit FIR-DAC, 24 + 8 * value, at most 64.
+    pub fn tap_count(&self) -> u8 {
+        24 + 8 * self.dac_bw.min(Self::DAC_BW_MAX)
+    }
+
+    /// Set [dac_bw](Self::dac_bw) for the tap count nearest to `taps`,
+    /// clamped to 24 through 64.
+    pub fn set_tap_count(&mut self, taps: u8) {
+        self.dac_bw = (taps.clamp(24, 64) - 24 + 4) / 8;
+    }
+
+    /// Analog filter 3 dB SSB bandwidth in KHz, half of the documented DSB
+    /// bandwidth.
+    fn filter_ssb_khz(&self) -> f32 {
+        self.filter_bw_mhz() * 1e3 / 2.0
+    }
+
+    /// FIR-DAC 3 dB SSB bandwidth in KHz. The data sheet only gives two
+    /// points, 450 KHz for 1 and 290 KHz for 5, so this interpolates linearly
+    /// between them. The tap count is capped at 64, so settings above 5 are
+    /// the same as 5.
+    fn fir_ssb_khz(dac_bw: u8) -> f32 {
+        450.0 - 40.0 * (dac_bw.min(5) as f32 - 1.0)
+    }
+
+    /// Check the analog filter and FIR-DAC bandwidths against each other and
+    /// against the SSB bandwidth of the transmitted signal in KHz.
+    ///
+    /// The analog filter is there to remove the quantization noise of the
+    /// FIR-DAC, so it must be wider than the FIR passband even at the bad end
+    /// of its 30% accuracy; otherwise it cuts into the signal the FIR passes
+    /// and adds group delay. Both must be wider than the signal.
+    pub fn joint_bandwidth_ok(&self, signal_bw_khz: f32) -> Result<(), Error> {
+        let filter = self.filter_ssb_khz() * (1.0 - Self::FILTER_ACCURACY);
+        let fir = Self::fir_ssb_khz(self.dac_bw);
+        let signal = signal_bw_khz * Self::BANDWIDTH_MARGIN;
+
+        if filter <= fir {
+            return Err(Error::InvalidRegisterValue("filter_bw"));
+        }
+        if fir < signal {
+            return Err(Error::InvalidRegisterValue("dac_bw"));
+        }
+        Ok(())
+    }
+
+    /// The [dac_bw](Self::dac_bw) setting with the fewest taps that should
+    /// meet `target_acpr_db` of adjacent-channel power ratio, for a signal of
+    /// `signal_bw_khz` SSB bandwidth on channels `channel_spacing_khz` apart.
+    ///
+    /// This is a first-order estimate: the FIR is treated as rolling off
+    /// 6 dB per octave for every 8 taps beyond its 3 dB bandwidth, measured
+    /// at the near edge of the adjacent channel. Settings whose passband is
+    /// narrower than the signal are never chosen. If no setting meets the
+    /// target, the most selective one that still passes the signal is
+    /// returned. Verify the result on a spectrum analyzer.
+    pub fn taps_for_acpr(signal_bw_khz: f32, channel_spacing_khz: f32, target_acpr_db: f32) -> u8 {
+        let edge = channel_spacing_khz - signal_bw_khz;
+        let mut best = 0;
+        for dac_bw in 0..=5 {
+            let bandwidth = Self::fir_ssb_khz(dac_bw);
+            if bandwidth < signal_bw_khz {
+                break;
+            }
+            best = dac_bw;
+            let taps = 24.0 + 8.0 * dac_bw as f32;
+            let rejection = if edge > bandwidth {
+                6.02 * (taps / 8.0) * (edge / bandwidth).log2()
+            } else {
+                0.0
+            };
+            if rejection >= target_acpr_db {
+                break;
+            }
+        }
+        best
+    }
+}
+
 #[repr(u8)]
-#[derive(Debug, BinarySerde, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, BinarySerde, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// Settings for [RxFrontend::zin]
 pub enum RxZIn {
     #[default]
@@ -188,19 +623,65 @@ pub enum RxZIn {
 }
 
 #[repr(u8)]
-#[derive(Debug, BinarySerde, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, BinarySerde, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// Settings for [RxFrontend::adc_bw]
 /// The SX1255 data sheet has a cryptic comment: "use 0x01 instead". This
 /// is not present in the SX1257 data sheet.
+///
+/// The raw values 7, 5, 2 and 1 aren't sequential because they are the only
+/// codes of the 3-bit field that the data sheets document; the others are
+/// reserved. The ranges overlap at their upper edges, so use
+/// [min_khz](Self::min_khz) and [for_bandwidth](Self::for_bandwidth) rather
+/// than the codes to convert to and from KHz.
 pub enum RxADCBw {
     #[default]
     BWOver400KHz = 7,
     BW200To400KHz = 5,
     BW100To400KHz = 2,
+    /// The code that the SX1255 data sheet says to use instead of
+    /// [BW100To400KHz](Self::BW100To400KHz). Not documented for SX1257.
+    BW100To400KHzSX1255 = 1,
+}
+
+impl RxADCBw {
+    /// The ADC bandwidth setting that gives the best SNR for a channel of
+    /// `channel_bw_khz` SSB bandwidth on `ic_version`. The delta-sigma ADC's
+    /// SNR improves with oversampling, so this is the narrowest setting that
+    /// still covers the channel, and channels narrower than every range get
+    /// the narrowest one. On the SX1255 that is code 0x01, as its data sheet
+    /// says.
+    pub fn optimal_for_channel(channel_bw_khz: u16, ic_version: ICVersion) -> RxADCBw {
+        match (Self::for_bandwidth(channel_bw_khz), ic_version) {
+            (RxADCBw::BW100To400KHz, ICVersion::SX1255) => RxADCBw::BW100To400KHzSX1255,
+            (adc_bw, _) => adc_bw,
+        }
+    }
+
+    /// The lower edge, in KHz, of this setting's bandwidth range.
+    pub fn min_khz(&self) -> u16 {
+        match self {
+            RxADCBw::BW100To400KHz | RxADCBw::BW100To400KHzSX1255 => 100,
+            RxADCBw::BW200To400KHz => 200,
+            RxADCBw::BWOver400KHz => 400,
+        }
+    }
+
+    /// The narrowest setting whose range covers `khz`, using only the codes
+    /// that both ICs document. Bandwidths below 100 KHz get the narrowest
+    /// setting, and those above 400 KHz the widest.
+    pub fn for_bandwidth(khz: u16) -> RxADCBw {
+        match khz {
+            0..200 => RxADCBw::BW100To400KHz,
+            200..=400 => RxADCBw::BW200To400KHz,
+            _ => RxADCBw::BWOver400KHz,
+        }
+    }
 }
 
 #[repr(u8)]
-#[derive(Debug, BinarySerde, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, BinarySerde, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// Settings for [RxFrontend::adc_trim]
 pub enum RxADCTrim {
     /// Value for use with a 32 MHz clock crystal.
@@ -212,7 +693,8 @@ pub enum RxADCTrim {
 }
 
 #[repr(u8)]
-#[derive(Debug, BinarySerde, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, BinarySerde, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// Settings for [RxFrontend::pga_bw]
 pub enum RxPGABw {
     #[default]
@@ -222,21 +704,98 @@ pub enum RxPGABw {
     BW500KHz = 3,
 }
 
-#[derive(Debug, Default, PartialEq, Eq)]
+impl RxPGABw {
+    /// The PGA bandwidth in KHz.
+    pub fn khz(&self) -> f32 {
+        match self {
+            RxPGABw::BW1500KHz => 1500.0,
+            RxPGABw::BW1000KHz => 1000.0,
+            RxPGABw::BW750KHz => 750.0,
+            RxPGABw::BW500KHz => 500.0,
+        }
+    }
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Hash, BinarySerde, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+/// The LNA gain steps of [RxFrontend::lna_gain], for use through
+/// [RxFrontend::lna] and [RxFrontend::set_lna]. Only codes 1 through 6 are
+/// used, so the illegal codes 0 and 7 can't be expressed. The noise figure
+/// and IP3 are specified at the highest gain, `G0dB`; reducing the gain
+/// trades sensitivity for strong-signal handling.
+pub enum RxLnaGain {
+    #[default]
+    G0dB = 1,
+    Gm6dB = 2,
+    Gm12dB = 3,
+    Gm24dB = 4,
+    Gm36dB = 5,
+    Gm48dB = 6,
+}
+
+impl RxLnaGain {
+    /// Every step, from the highest gain to the lowest.
+    const ALL: [RxLnaGain; 6] = [
+        RxLnaGain::G0dB,
+        RxLnaGain::Gm6dB,
+        RxLnaGain::Gm12dB,
+        RxLnaGain::Gm24dB,
+        RxLnaGain::Gm36dB,
+        RxLnaGain::Gm48dB,
+    ];
+
+    /// The step for a register code. The unused codes 0 and 7 are read as
+    /// their nearest neighbors.
+    pub fn from_code(code: u8) -> RxLnaGain {
+        Self::ALL.into_iter().nth(code.clamp(1, 6) as usize - 1).unwrap_or_default()
+    }
+
+    /// The gain in dB. The steps are not even, they are 6 dB at first and
+    /// then 12 dB.
+    pub fn gain_db(&self) -> f32 {
+        match self {
+            RxLnaGain::G0dB => 0.0,
+            RxLnaGain::Gm6dB => -6.0,
+            RxLnaGain::Gm12dB => -12.0,
+            RxLnaGain::Gm24dB => -24.0,
+            RxLnaGain::Gm36dB => -36.0,
+            RxLnaGain::Gm48dB => -48.0,
+        }
+    }
+
+    /// The step nearest to `db`. Anything above 0 dB gets the highest gain,
+    /// anything below -48 dB the lowest, and a tie the higher gain.
+    pub fn nearest(db: f32) -> RxLnaGain {
+        Self::ALL
+            .into_iter()
+            .min_by(|a, b| (db - a.gain_db()).abs().total_cmp(&(db - b.gain_db()).abs()))
+            .unwrap_or_default()
+    }
+
+    /// Whether this is the step with the best noise figure, the highest
+    /// gain. An AGC should return here whenever the signal allows.
+    pub fn is_best_for_sensitivity(&self) -> bool {
+        *self == RxLnaGain::G0dB
+    }
+}
+
+#[derive(Debug, Clone, Hash, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[binary_serde_bitfield(order = BitfieldBitOrder::MsbFirst)]
 /// SX1255 hardware receive front-end control register.
 pub struct RxFrontend {
     #[bits(3)]
-    /// Receive LNA gain. Values 0 and 7 are not used. Value 1 is 0 dB, and
-    /// gain descends in -6 dB steps until value 6 is -48 dB
+    /// Receive LNA gain. Values 0 and 7 are not used. Values 1 through 6 are
+    /// 0, -6, -12, -24, -36 and -48 dB.
     /// This effects both the receiver noise figure and IP3, receiver performance
     /// will be best with this value at minimum, see the RX Front-End
-    /// specification in the data sheet.
+    /// specification in the data sheet. See [RxLnaGain] for the steps.
     pub lna_gain: u8,
 
     #[bits(4)]
     /// Receive baseband amplifier gain.
-    /// gain = lowest gain + (2 dB * value)
+    /// gain = lowest gain + (2 dB * value), the lowest gain being -24 dB.
     pub baseband_gain: u8,
 
     #[bits(1)]
@@ -275,8 +834,163 @@ pub struct RxFrontend {
     pub adc_temp: bool,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A set of receive gains, in dB, applied together by
+/// [RxFrontend::set_gains].
+pub struct GainSet {
+    /// LNA gain, 0 to -48 dB.
+    pub lna_db: f32,
+    /// Baseband amplifier gain, -24 to 6 dB in 2 dB steps.
+    pub baseband_db: f32,
+}
+
+impl RxFrontend {
+    /// Baseband gain in dB for code 0, it increases 2 dB per step.
+    const BASEBAND_GAIN_MIN_DB: f32 = -24.0;
+    const BASEBAND_GAIN_MAX_CODE: u8 = 15;
+
+    /// The LNA gain code nearest to `db`, see [RxLnaGain::nearest]. 0 and 7
+    /// are never produced.
+    pub(crate) fn lna_gain_code(db: f32) -> u8 {
+        RxLnaGain::nearest(db) as u8
+    }
+
+    /// The LNA gain in dB for `code`. The unused codes 0 and 7 are read as
+    /// their nearest neighbors.
+    pub(crate) fn lna_gain_from_code(code: u8) -> f32 {
+        RxLnaGain::from_code(code).gain_db()
+    }
+
+    fn lna_gain_in_range(db: f32) -> bool {
+        (RxLnaGain::Gm48dB.gain_db()..=RxLnaGain::G0dB.gain_db()).contains(&db)
+    }
+
+    /// The baseband gain code nearest to `db`, clamped to the legal range.
+    pub(crate) fn baseband_gain_code(db: f32) -> u8 {
+        ((db - Self::BASEBAND_GAIN_MIN_DB) / 2.0)
+            .round()
+            .clamp(0.0, Self::BASEBAND_GAIN_MAX_CODE as f32) as u8
+    }
+
+    /// The baseband gain in dB for `code`.
+    pub(crate) fn baseband_gain_from_code(code: u8) -> f32 {
+        Self::BASEBAND_GAIN_MIN_DB + 2.0 * code.min(Self::BASEBAND_GAIN_MAX_CODE) as f32
+    }
+
+    fn baseband_gain_in_range(db: f32) -> bool {
+        let max = Self::BASEBAND_GAIN_MIN_DB + 2.0 * Self::BASEBAND_GAIN_MAX_CODE as f32;
+        (Self::BASEBAND_GAIN_MIN_DB..=max).contains(&db)
+    }
+
+    /// Set [lna_gain](Self::lna_gain) to the step nearest to `db`, clamped to
+    /// 0 to -48 dB. The unused codes 0 and 7 are never set.
+    pub fn set_lna_gain_db(&mut self, db: f32) {
+        self.lna_gain = Self::lna_gain_code(db);
+    }
+
+    /// The LNA gain in dB.
+    pub fn lna_gain_db(&self) -> f32 {
+        Self::lna_gain_from_code(self.lna_gain)
+    }
+
+    /// The LNA gain step. The unused codes 0 and 7 are read as their nearest
+    /// neighbors.
+    pub fn lna(&self) -> RxLnaGain {
+        RxLnaGain::from_code(self.lna_gain)
+    }
+
+    /// Set [lna_gain](Self::lna_gain) to `gain`.
+    pub fn set_lna(&mut self, gain: RxLnaGain) {
+        self.lna_gain = gain as u8;
+    }
+
+    /// Set [baseband_gain](Self::baseband_gain) to the 2 dB step nearest to
+    /// `db`, clamped to -24 to +6 dB.
+    pub fn set_baseband_gain_db(&mut self, db: f32) {
+        self.baseband_gain = Self::baseband_gain_code(db);
+    }
+
+    /// The baseband gain in dB.
+    pub fn baseband_gain_db(&self) -> f32 {
+        Self::baseband_gain_from_code(self.baseband_gain)
+    }
+
+    /// Typical receiver noise figure in dB at the highest LNA gain.
+    const NOISE_FIGURE_DB: f32 = 4.5;
+
+    /// Estimate the receiver sensitivity in dBm for a signal that needs
+    /// `required_snr_db` to be demodulated, with the classic
+    /// -174 dBm/Hz + NF + 10 log10(bandwidth) + SNR.
+    ///
+    /// The noise bandwidth is that of the PGA filter, taken on both sides of
+    /// the I/Q baseband. The noise figure is the typical figure at the highest
+    /// LNA gain; once the LNA gain is reduced by more than 6 dB, the stages
+    /// after it dominate and the noise figure is taken to rise dB for dB.
+    /// This is an estimate for comparing configurations, not a measurement.
+    pub fn sensitivity_dbm(&self, required_snr_db: f32) -> f32 {
+        let reduction = -Self::lna_gain_from_code(self.lna_gain);
+        let noise_figure = Self::NOISE_FIGURE_DB + (reduction - 6.0).max(0.0);
+        let bandwidth_hz = 2.0 * self.pga_bw.khz() * 1e3;
+        -174.0 + noise_figure + 10.0 * bandwidth_hz.log10() + required_snr_db
+    }
+
+    /// Typical SNR in dB of the receive ADC with its widest bandwidth setting.
+    const ADC_SNR_WIDEST_DB: f32 = 60.0;
+
+    /// Estimate the effective number of bits of the receive path, from the
+    /// SNR with the classic ENOB = (SNR - 1.76) / 6.02.
+    ///
+    /// The delta-sigma ADC runs at a fixed clock, so a narrower
+    /// [adc_bw](Self::adc_bw) is a higher oversampling ratio, and the noise
+    /// that reaches the output falls with the bandwidth. The noise bandwidth
+    /// is the lower edge of the ADC setting's range, or the PGA filter if
+    /// that is narrower, and the SNR rises 10 log10 of its ratio to the
+    /// widest setting's 400 KHz. Like [sensitivity_dbm](Self::sensitivity_dbm)
+    /// this is an estimate for comparing configurations, not a measurement.
+    pub fn enob(&self) -> f32 {
+        let bandwidth_khz = (self.adc_bw.min_khz() as f32).min(self.pga_bw.khz());
+        let snr = Self::ADC_SNR_WIDEST_DB + 10.0 * (400.0 / bandwidth_khz).log10();
+        (snr - 1.76) / 6.02
+    }
+
+    /// Convert a raw receive ADC reading taken with
+    /// [adc_temp](Self::adc_temp) set into degrees Celsius, at the
+    /// documented -1 °C/LSB. `calibration_offset` is the temperature that
+    /// would read as 0: calibrate it once as the externally measured
+    /// temperature of the IC plus the raw reading taken at that time.
+    pub fn temperature_celsius(raw: u8, calibration_offset: f32) -> f32 {
+        calibration_offset - raw as f32
+    }
+
+    /// Convert a raw temperature reading taken with this configuration, as
+    /// [temperature_celsius](Self::temperature_celsius) does. The data sheet
+    /// gives the one -1 °C/LSB slope for both [adc_trim](Self::adc_trim)
+    /// settings: the trim matches the ADC to the crystal, so the reading is
+    /// the same on a 32 MHz or a 36 MHz crystal and no correction is applied.
+    /// A calibration taken on one crystal holds for the other.
+    pub fn measure_temperature(&self, raw: u8, calibration_offset: f32) -> f32 {
+        Self::temperature_celsius(raw, calibration_offset)
+    }
+
+    /// Set the LNA and baseband gains together. Both values are checked
+    /// before either is changed, so an error leaves the register untouched.
+    /// Values between steps are rounded to the nearest step.
+    pub fn set_gains(&mut self, gains: GainSet) -> Result<(), Error> {
+        if !Self::lna_gain_in_range(gains.lna_db) {
+            return Err(Error::InvalidRegisterValue("lna_gain"));
+        }
+        if !Self::baseband_gain_in_range(gains.baseband_db) {
+            return Err(Error::InvalidRegisterValue("baseband_gain"));
+        }
+        self.lna_gain = Self::lna_gain_code(gains.lna_db);
+        self.baseband_gain = Self::baseband_gain_code(gains.baseband_db);
+        Ok(())
+    }
+}
+
 #[repr(u8)]
-#[derive(BinarySerde, Debug, Default, Eq, PartialEq)]
+#[derive(BinarySerde, Debug, Clone, Hash, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// Values for [IOMap::iomap0]. Do we want to see PLL lock Rx on DIO pin 0,
 /// or EOL (which indicates battery low).
 pub enum IOMap0 {
@@ -289,7 +1003,8 @@ pub enum IOMap0 {
 }
 
 #[repr(u8)]
-#[derive(BinarySerde, Debug, Default, Eq, PartialEq)]
+#[derive(BinarySerde, Debug, Clone, Hash, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// Values for [IOMap::iomap1]. Only one documented setting.
 pub enum IOMap1 {
   #[default]
@@ -298,7 +1013,8 @@ pub enum IOMap1 {
 }
 
 #[repr(u8)]
-#[derive(BinarySerde, Debug, Default, Eq, PartialEq)]
+#[derive(BinarySerde, Debug, Clone, Hash, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// Values for [IOMap::iomap2]. Only one documented setting.
 pub enum IOMap2 {
   #[default]
@@ -307,7 +1023,8 @@ pub enum IOMap2 {
 }
 
 #[repr(u8)]
-#[derive(BinarySerde, Debug, Default, Eq, PartialEq)]
+#[derive(BinarySerde, Debug, Clone, Hash, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// Values for [IOMap::iomap3]. Only one documented setting.
 pub enum IOMap3 {
   #[default]
@@ -319,7 +1036,8 @@ pub enum IOMap3 {
   PLLLockRxTx = 0,
 }
 
-#[derive(Debug, Default, Eq, PartialEq)]
+#[derive(Debug, Clone, Hash, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[binary_serde_bitfield(order = BitfieldBitOrder::MsbFirst)]
 /// SX1255 hardware mapping of the 4 DIO pins. This isn't as useful as it
 /// might be, as documented.
@@ -347,8 +1065,34 @@ pub struct IOMap {
     pub iomap3: IOMap3,
 }
 
+impl IOMap {
+    /// The mapping for the indications wanted on DIO pins. Only DIO0 has a
+    /// choice, so the other pins are the same in every mapping:
+    ///
+    /// | Pin  | Separate PLL locks | EOL |
+    /// |------|--------------------|-----|
+    /// | DIO0 | PLL lock Rx | EOL, battery low |
+    /// | DIO1 | PLL lock Tx | PLL lock Tx |
+    /// | DIO2 | Oscillator ready | Oscillator ready |
+    /// | DIO3 | PLL lock Rx or Tx, by mode | PLL lock Rx or Tx, by mode |
+    ///
+    /// With `want_eol` alone, the PLL locks are left multiplexed on DIO3 and
+    /// DIO0 shows battery low. Otherwise, including when both are wanted and
+    /// can't both be had, the recommended mapping is returned: separate PLL
+    /// locks, with battery low polled from the status register over SPI.
+    pub fn for_indications(want_separate_pll_locks: bool, want_eol: bool) -> IOMap {
+        let iomap0 = if want_eol && !want_separate_pll_locks {
+            IOMap0::Eol
+        } else {
+            IOMap0::PLLLockRx
+        };
+        IOMap { iomap0, ..Default::default() }
+    }
+}
+
 #[repr(u8)]
-#[derive(BinarySerde, Debug, Default, Eq, PartialEq)]
+#[derive(BinarySerde, Debug, Clone, Hash, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// Values for [ClockSelect::clock_select_tx_dac]
 /// This selects the clock for the transmit DAC only. For synchronization,
 /// it's recommended to use the internal clock, so that the transmit DAC
@@ -359,7 +1103,8 @@ pub enum ClockSelectTxDAC {
   External = 1,
 }
 
-#[derive(Debug, Default, Eq, PartialEq)]
+#[derive(Debug, Clone, Hash, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[binary_serde_bitfield(order = BitfieldBitOrder::MsbFirst)]
 /// SX1255 clock select register.
 pub struct ClockSelect {
@@ -387,7 +1132,8 @@ pub struct ClockSelect {
     pub clock_select_tx_dac: ClockSelectTxDAC,
 }
 
-#[derive(Debug, Default, Eq, PartialEq)]
+#[derive(Debug, Clone, Hash, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[binary_serde_bitfield(order = BitfieldBitOrder::MsbFirst)]
 /// SX1255 hardware mapping of status bits.
 pub struct Status {
@@ -412,8 +1158,16 @@ pub struct Status {
     pub pll_lock_tx: bool,
 }
 
+impl Status {
+    /// Decode the raw value of the status register.
+    pub fn from_raw(raw: u8) -> Status {
+        Status::binary_deserialize(&[raw], Endianness::Big).expect("every byte decodes")
+    }
+}
+
 #[repr(u8)]
-#[derive(BinarySerde, Debug, Default, Eq, PartialEq)]
+#[derive(BinarySerde, Debug, Clone, Hash, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// Values for [IISM::mode]. This register is documented for SX1255 but
 /// not for SX1257, thus SX1257 is always in mode A. Since it was dropped
 /// in SX1257, this may be an indication that this feature is problematic.
@@ -434,7 +1188,8 @@ pub enum IISMMode {
   /// DIO2 carries the WS pin, WS is one CLOCK_OUT period ahead of time.
   ///
   /// See the datasheet section on TX Noise Shaper to understand the
-  /// preprocessing requirements for transmit data.
+  /// preprocessing requirements for transmit data, and
+  /// [TxNoiseShaper](crate::dsp::TxNoiseShaper).
   B1 = 1,
  
   /// In mode B2, the IQ signals are pre and post-processed by the
@@ -449,12 +1204,14 @@ pub enum IISMMode {
   /// I²S implementations of CPUs.
   ///
   /// See the datasheet section on TX Noise Shaper to understand the
-  /// preprocessing requirements for transmit data.
+  /// preprocessing requirements for transmit data, and
+  /// [TxNoiseShaper](crate::dsp::TxNoiseShaper).
   B2 = 2,
 }
 
 #[repr(u8)]
-#[derive(BinarySerde, Debug, Default, Eq, PartialEq)]
+#[derive(BinarySerde, Debug, Clone, Hash, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// Values for [IISM::clock_div]. This is the oscillator_frequency / CLK_OUT
 /// division factor. This feature is not documented for SX1257.
 pub enum IISMClockDiv {
@@ -470,7 +1227,51 @@ pub enum IISMClockDiv {
   D64 = 8,
 }
 
-#[derive(Debug, Default, Eq, PartialEq)]
+impl IISMClockDiv {
+    const ALL: [IISMClockDiv; 9] = [
+        IISMClockDiv::D0,
+        IISMClockDiv::D2,
+        IISMClockDiv::D4,
+        IISMClockDiv::D8,
+        IISMClockDiv::D12,
+        IISMClockDiv::D16,
+        IISMClockDiv::D24,
+        IISMClockDiv::D32,
+        IISMClockDiv::D64,
+    ];
+
+    /// The division factor, or `None` for D0, which doesn't divide: CLK_OUT
+    /// is then the oscillator itself.
+    pub fn divisor(&self) -> Option<u32> {
+        match self {
+            IISMClockDiv::D0 => None,
+            IISMClockDiv::D2 => Some(2),
+            IISMClockDiv::D4 => Some(4),
+            IISMClockDiv::D8 => Some(8),
+            IISMClockDiv::D12 => Some(12),
+            IISMClockDiv::D16 => Some(16),
+            IISMClockDiv::D24 => Some(24),
+            IISMClockDiv::D32 => Some(32),
+            IISMClockDiv::D64 => Some(64),
+        }
+    }
+
+    /// The setting with the divisor nearest to `target_divisor`, with D0
+    /// counted as a divisor of 1. On a tie, the smaller divisor is chosen,
+    /// for the faster clock.
+    pub fn nearest(target_divisor: u32) -> IISMClockDiv {
+        Self::ALL
+            .into_iter()
+            .min_by_key(|div| {
+                let divisor = div.divisor().unwrap_or(1);
+                (divisor.abs_diff(target_divisor), divisor)
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Hash, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[binary_serde_bitfield(order = BitfieldBitOrder::MsbFirst)]
 /// SX1255 hardware mapping of IO control. This feature is not documented
 /// for SX1257.
@@ -494,8 +1295,82 @@ pub struct IISM {
   pub clock_div: IISMClockDiv
 }
 
+impl IISM {
+    /// The CLK_OUT frequency presented to the host, for setting up its I²S
+    /// clocks. `oscillator_hz` should be the measured oscillator frequency,
+    /// not the nominal one. D0 passes the oscillator through undivided.
+    pub fn clk_out_hz(&self, oscillator_hz: f64) -> f64 {
+        oscillator_hz / self.clock_div.divisor().unwrap_or(1) as f64
+    }
+
+    /// Check that this setting doesn't break `mode`. In full duplex, both
+    /// I²S directions must run at once, and
+    /// [rx_during_tx_disable](Self::rx_during_tx_disable) or
+    /// [tx_during_rx_disable](Self::tx_during_rx_disable) would silently
+    /// stop one of them. The error names the offending field. Other modes
+    /// don't run both directions, and pass.
+    pub fn check_duplex(&self, mode: &crate::control::Mode) -> Result<(), Error> {
+        if *mode != crate::control::Mode::FullDuplex {
+            return Ok(());
+        }
+        if self.rx_during_tx_disable {
+            return Err(Error::InvalidRegisterValue("rx_during_tx_disable"));
+        }
+        if self.tx_during_rx_disable {
+            return Err(Error::InvalidRegisterValue("tx_during_rx_disable"));
+        }
+        Ok(())
+    }
+
+    /// How many CLOCK_OUT periods WS changes ahead of the data it frames. In
+    /// Modes B1 and B2 this is the documented one period, which is the WS
+    /// lead of Philips-standard I²S, so a host set up for standard I²S
+    /// needs no further compensation; a host set up for left-justified
+    /// framing must delay its WS by this many bit clocks. The lead is less
+    /// than one sample, so this is the only compensation needed: the I and
+    /// Q samples then arrive in the slots WS marks. Mode A has no WS, and
+    /// returns 0.
+    pub fn ws_lead_periods(&self) -> i8 {
+        match self.mode {
+            IISMMode::A => 0,
+            IISMMode::B1 | IISMMode::B2 => 1,
+        }
+    }
+
+    /// How many samples the data line carries per WS period. In Mode B1, the
+    /// I and Q lines each carry one sample per period; in Mode B2, the one
+    /// line carries an I sample while WS is 0 and a Q sample while WS is 1,
+    /// see [interleave_b2] and [deinterleave_b2]. Mode A has no WS, and
+    /// returns 0.
+    pub fn samples_per_ws(&self) -> u8 {
+        match self.mode {
+            IISMMode::A => 0,
+            IISMMode::B1 => 1,
+            IISMMode::B2 => 2,
+        }
+    }
+}
+
+/// Interleave I and Q samples onto the single data line of Mode B2, I first,
+/// as WS 0 precedes WS 1. If the slices differ in length, the extra samples
+/// of the longer one are dropped. Align the samples to the bus with
+/// [IISMTruncation::to_bus] first.
+#[cfg(feature = "alloc")]
+pub fn interleave_b2(i: &[i32], q: &[i32]) -> Vec<i32> {
+    i.iter().zip(q).flat_map(|(i, q)| [*i, *q]).collect()
+}
+
+/// Split the single data line of Mode B2 into I and Q samples, the inverse
+/// of [interleave_b2]. The buffer should start on a WS 0 sample; a trailing
+/// I sample without its Q is dropped.
+#[cfg(feature = "alloc")]
+pub fn deinterleave_b2(samples: &[i32]) -> (Vec<i32>, Vec<i32>) {
+    samples.chunks_exact(2).map(|pair| (pair[0], pair[1])).unzip()
+}
+
 #[repr(u8)]
-#[derive(BinarySerde, Debug, Default, Eq, PartialEq)]
+#[derive(BinarySerde, Debug, Clone, Hash, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// Values for [DigitalBridge::int_dec_mantissa]. This feature is not
 /// documented for SX1257.
 pub enum IntDecMantissa {
@@ -512,7 +1387,8 @@ pub enum IntDecMantissa {
 }
 
 #[repr(u8)]
-#[derive(BinarySerde, Debug, Default, Eq, PartialEq)]
+#[derive(BinarySerde, Debug, Clone, Hash, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// Values for [DigitalBridge::iism_truncation]. This feature is not documented
 /// for SX1257.
 pub enum IISMTruncation {
@@ -524,7 +1400,30 @@ pub enum IISMTruncation {
   LSB = 1,
 }
 
-#[derive(Debug, Default, Eq, PartialEq)]
+impl IISMTruncation {
+    /// Place a sample of `bits` effective bits on the 32-bit bus: as is for
+    /// [MSB](Self::MSB), aligned upon the LSB, or shifted up for
+    /// [LSB](Self::LSB), aligned upon the MSB.
+    pub fn to_bus(&self, sample: i32, bits: u8) -> i32 {
+        match self {
+            IISMTruncation::MSB => sample,
+            IISMTruncation::LSB => sample << (32 - bits.clamp(1, 32) as u32),
+        }
+    }
+
+    /// Recover a sample of `bits` effective bits from a 32-bit bus word, the
+    /// inverse of [to_bus](Self::to_bus). The result is sign-extended.
+    pub fn from_bus(&self, word: i32, bits: u8) -> i32 {
+        let shift = 32 - bits.clamp(1, 32) as u32;
+        match self {
+            IISMTruncation::MSB => (word << shift) >> shift,
+            IISMTruncation::LSB => word >> shift,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Hash, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[binary_serde_bitfield(order = BitfieldBitOrder::MsbFirst)]
 /// Digital bridge interpolation/decimation facility. This feature is not
 /// documented for SX1257.
@@ -569,8 +1468,68 @@ pub struct DigitalBridge {
     _unused: (),
 }
 
+impl DigitalBridge {
+    /// The largest legal value of [DigitalBridge::int_dec_n_parameter].
+    pub const MAX_N: u8 = 6;
+
+    /// The interpolation/decimation factor, mantissa * 3^m * 2^n, or `None`
+    /// if m or n are out of range.
+    pub fn factor(&self) -> Option<u32> {
+        if self.int_dec_m_parameter > 1 || self.int_dec_n_parameter > Self::MAX_N {
+            return None;
+        }
+        let mantissa = match self.int_dec_mantissa {
+            IntDecMantissa::M8 => 8,
+            IntDecMantissa::M9 => 9,
+        };
+        Some((mantissa * 3u32.pow(self.int_dec_m_parameter as u32)) << self.int_dec_n_parameter)
+    }
+
+    /// All of the legal mantissa/m/n settings.
+    fn settings() -> impl Iterator<Item = DigitalBridge> {
+        (0..=Self::MAX_N).flat_map(|n| {
+            (0..=1u8).flat_map(move |m| {
+                [IntDecMantissa::M8, IntDecMantissa::M9].into_iter().map(move |mantissa| {
+                    DigitalBridge {
+                        int_dec_mantissa: mantissa,
+                        int_dec_m_parameter: m,
+                        int_dec_n_parameter: n,
+                        ..Default::default()
+                    }
+                })
+            })
+        })
+    }
+
+    /// The setting giving exactly `target` as the interpolation/decimation
+    /// factor, or `None` if there is no such setting.
+    pub fn from_factor(target: u32) -> Option<DigitalBridge> {
+        Self::settings().find(|bridge| bridge.factor() == Some(target))
+    }
+
+    /// The setting that interpolates transmit data by exactly `ratio`, for a
+    /// chosen oversampling ratio. The caller chooses the sample depth for the
+    /// transmit noise shaper from the data sheet. `None` if no setting
+    /// interpolates by `ratio`.
+    pub fn for_transmit_oversampling(ratio: u32) -> Option<DigitalBridge> {
+        Self::from_factor(ratio)
+    }
+
+    /// The setting with the factor nearest to `target`. On a tie, the lower
+    /// factor is chosen.
+    pub fn nearest_factor(target: u32) -> DigitalBridge {
+        Self::settings()
+            .min_by_key(|bridge| {
+                let factor = bridge.factor().unwrap_or(0);
+                (factor.abs_diff(target), factor)
+            })
+            .unwrap_or_default()
+    }
+}
+
 #[repr(u8)]
-#[derive(BinarySerde, Debug, Default, Eq, PartialEq)]
+#[derive(BinarySerde, Debug, Clone, Hash, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// Values for [LowBatteryThreshold::threshold]
 /// This feature is only documented for SX1257.
 pub enum ThresholdValue {
@@ -593,7 +1552,45 @@ pub enum ThresholdValue {
   V3_245 = 7,
 }
 
-#[derive(Debug, Default, Eq, PartialEq)]
+impl ThresholdValue {
+    /// Every threshold, in register code order, which is ascending voltage.
+    const ALL: [ThresholdValue; 8] = [
+        ThresholdValue::V2_516,
+        ThresholdValue::V2_619,
+        ThresholdValue::V2_724,
+        ThresholdValue::V2_829,
+        ThresholdValue::V2_935,
+        ThresholdValue::V3_037,
+        ThresholdValue::V3_143,
+        ThresholdValue::V3_245,
+    ];
+
+    /// The threshold in volts.
+    pub fn volts(&self) -> f32 {
+        match self {
+            ThresholdValue::V2_516 => 2.516,
+            ThresholdValue::V2_619 => 2.619,
+            ThresholdValue::V2_724 => 2.724,
+            ThresholdValue::V2_829 => 2.829,
+            ThresholdValue::V2_935 => 2.935,
+            ThresholdValue::V3_037 => 3.037,
+            ThresholdValue::V3_143 => 3.143,
+            ThresholdValue::V3_245 => 3.245,
+        }
+    }
+
+    /// The threshold nearest to `volts`. Requests outside of 2.516 to
+    /// 3.245 volts get the nearest end of the range.
+    pub fn nearest(volts: f32) -> ThresholdValue {
+        Self::ALL
+            .into_iter()
+            .min_by(|a, b| (volts - a.volts()).abs().total_cmp(&(volts - b.volts()).abs()))
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Hash, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[binary_serde_bitfield(order = BitfieldBitOrder::MsbFirst)]
 /// SX1257 hardware mapping of low battery threshold register, at
 /// location 0x1A. This is not documented for SX1255.
@@ -606,7 +1603,8 @@ pub struct LowBatteryThreshold {
 }
 
 #[doc = include_str!("../markdown/hard_registers.md")]
-#[derive(Debug, Default, Eq, PartialEq)]
+#[derive(Debug, Clone, Hash, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct HardRegisters {
     pub mode: Mode,
     pub rx: Frequency,
@@ -624,49 +1622,754 @@ pub struct HardRegisters {
 }
 
 #[repr(u8)]
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ICVersion {
     #[default]
     SX1255 = 0,
     SX1257 = 1,
 }
 
+impl ICVersion {
+    /// The specified RF band of the IC, `(low_hz, high_hz)`. SX1255 is
+    /// specified for 400-512 MHz, SX1257 for 860-1000 MHz. The VCO runs at
+    /// four times the RF frequency on SX1255 and twice on SX1257, so these
+    /// are also the limits of the VCO.
+    pub const fn rf_range_hz(&self) -> (f64, f64) {
+        match self {
+            ICVersion::SX1255 => (400e6, 512e6),
+            ICVersion::SX1257 => (860e6, 1000e6),
+        }
+    }
+
+    /// The power of two that divides the oscillator frequency into the
+    /// frequency step: 20 for SX1255, 19 for SX1257.
+    pub const fn frequency_divisor_bits(&self) -> u32 {
+        match self {
+            ICVersion::SX1255 => 20,
+            ICVersion::SX1257 => 19,
+        }
+    }
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+/// The address of each register of the IC, for single-register access.
+/// Multi-byte fields of [HardRegisters] span consecutive addresses, most
+/// significant byte first.
+pub enum RegisterAddr {
+    /// [Mode].
+    Mode = 0x00,
+    /// Most significant byte of [HardRegisters::rx].
+    RxFreqMsb = 0x01,
+    /// Middle byte of [HardRegisters::rx].
+    RxFreqMid = 0x02,
+    /// Least significant byte of [HardRegisters::rx]. Writing it latches the
+    /// receive frequency.
+    RxFreqLsb = 0x03,
+    /// Most significant byte of [HardRegisters::tx].
+    TxFreqMsb = 0x04,
+    /// Middle byte of [HardRegisters::tx].
+    TxFreqMid = 0x05,
+    /// Least significant byte of [HardRegisters::tx]. Writing it latches the
+    /// transmit frequency.
+    TxFreqLsb = 0x06,
+    /// [Version], read only.
+    Version = 0x07,
+    /// [TxFrontend], the transmit gains.
+    TxGain = 0x08,
+    /// The mixer tank byte of [TxFrontend1255], SX1255 only.
+    TxMixerTank = 0x09,
+    /// The PLL and analog filter byte of [TxFrontend1255], SX1255 only.
+    TxBw = 0x0A,
+    /// The FIR-DAC byte of [TxFrontend1255], SX1255 only.
+    TxDacBw = 0x0B,
+    /// The gain byte of [RxFrontend].
+    RxGain = 0x0C,
+    /// The ADC and PGA bandwidth byte of [RxFrontend].
+    RxBw = 0x0D,
+    /// The PLL bandwidth and temperature byte of [RxFrontend].
+    RxPllBw = 0x0E,
+    /// [IOMap].
+    DioMapping = 0x0F,
+    /// [ClockSelect].
+    ClockSelect = 0x10,
+    /// [Status], read only.
+    Status = 0x11,
+    /// [IISM], SX1255 only.
+    Iism = 0x12,
+    /// [DigitalBridge], SX1255 only.
+    DigitalBridge = 0x13,
+    /// [LowBatteryThreshold], SX1257 only.
+    LowBatteryThreshold = 0x1A,
+}
+
+impl RegisterAddr {
+    /// Every register, in ascending address order.
+    pub const ALL: [RegisterAddr; 21] = [
+        RegisterAddr::Mode,
+        RegisterAddr::RxFreqMsb,
+        RegisterAddr::RxFreqMid,
+        RegisterAddr::RxFreqLsb,
+        RegisterAddr::TxFreqMsb,
+        RegisterAddr::TxFreqMid,
+        RegisterAddr::TxFreqLsb,
+        RegisterAddr::Version,
+        RegisterAddr::TxGain,
+        RegisterAddr::TxMixerTank,
+        RegisterAddr::TxBw,
+        RegisterAddr::TxDacBw,
+        RegisterAddr::RxGain,
+        RegisterAddr::RxBw,
+        RegisterAddr::RxPllBw,
+        RegisterAddr::DioMapping,
+        RegisterAddr::ClockSelect,
+        RegisterAddr::Status,
+        RegisterAddr::Iism,
+        RegisterAddr::DigitalBridge,
+        RegisterAddr::LowBatteryThreshold,
+    ];
+
+    /// The register at `addr`, or `None` for an address with no documented
+    /// register.
+    pub fn from_addr(addr: u8) -> Option<RegisterAddr> {
+        Self::ALL.into_iter().find(|register| *register as u8 == addr)
+    }
+
+    /// Whether the register should be written on `ic_version`. The version
+    /// and status registers are read-only, and registers that aren't
+    /// documented for the IC are left alone.
+    pub fn writable(&self, ic_version: ICVersion) -> bool {
+        use RegisterAddr as R;
+        match self {
+            R::Version | R::Status => false,
+            R::TxMixerTank | R::TxBw | R::TxDacBw | R::Iism | R::DigitalBridge => {
+                ic_version == ICVersion::SX1255
+            }
+            R::LowBatteryThreshold => ic_version == ICVersion::SX1257,
+            _ => true,
+        }
+    }
+
+    /// The address as an index into a serialized register bank.
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    /// The name of the register, as in this enumeration.
+    pub const fn name(self) -> &'static str {
+        use RegisterAddr as R;
+        match self {
+            R::Mode => "Mode",
+            R::RxFreqMsb => "RxFreqMsb",
+            R::RxFreqMid => "RxFreqMid",
+            R::RxFreqLsb => "RxFreqLsb",
+            R::TxFreqMsb => "TxFreqMsb",
+            R::TxFreqMid => "TxFreqMid",
+            R::TxFreqLsb => "TxFreqLsb",
+            R::Version => "Version",
+            R::TxGain => "TxGain",
+            R::TxMixerTank => "TxMixerTank",
+            R::TxBw => "TxBw",
+            R::TxDacBw => "TxDacBw",
+            R::RxGain => "RxGain",
+            R::RxBw => "RxBw",
+            R::RxPllBw => "RxPllBw",
+            R::DioMapping => "DioMapping",
+            R::ClockSelect => "ClockSelect",
+            R::Status => "Status",
+            R::Iism => "Iism",
+            R::DigitalBridge => "DigitalBridge",
+            R::LowBatteryThreshold => "LowBatteryThreshold",
+        }
+    }
+
+    /// The indices of the registers from `first` through `last`.
+    const fn span(first: RegisterAddr, last: RegisterAddr) -> RangeInclusive<usize> {
+        first.index()..=last.index()
+    }
+
+    /// The indices of the registers that must be written together with this
+    /// one. A frequency takes effect when its least significant byte is
+    /// written, so its three bytes go together; others stand alone.
+    fn latch_group(self) -> RangeInclusive<usize> {
+        use RegisterAddr as R;
+        match self {
+            R::RxFreqMsb | R::RxFreqMid | R::RxFreqLsb => Self::span(R::RxFreqMsb, R::RxFreqLsb),
+            R::TxFreqMsb | R::TxFreqMid | R::TxFreqLsb => Self::span(R::TxFreqMsb, R::TxFreqLsb),
+            _ => Self::span(self, self),
+        }
+    }
+}
+
+/// The reset values of the SX1255 registers, from the register table of the
+/// data sheet, for use in `const` contexts. [Sx125x::reset] writes these, and
+/// [HardRegisters::default_for] decodes them.
+///
+/// [Sx125x::reset]: crate::transport::Sx125x::reset
+pub const DEFAULT_REGISTERS_SX1255: [u8; 0x1B] = [
+    0x00,               // Mode: sleep.
+    0xC0, 0xE3, 0x8E,   // Receive frequency.
+    0xC0, 0xE3, 0x8E,   // Transmit frequency.
+    0x1A,               // Version.
+    0x2E,               // Transmit gain: DAC -3 dB, mixer -9.5 dB.
+    0x00,               // Mixer tank.
+    0x60,               // Transmit PLL 300 KHz, filter bandwidth code 0.
+    0x02,               // DAC FIR, 40 taps.
+    0x3F,               // Highest LNA and baseband gain, 200 Ω input.
+    0xF5,               // ADC bandwidth over 400 KHz, 36 MHz trim, PGA 1000 KHz.
+    0x06,               // Receive PLL 300 KHz.
+    0x00,               // DIO mapping.
+    0x02,               // Clock output enabled.
+    0x00,               // Status.
+    0x00,               // IISM.
+    0x00,               // Digital bridge.
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+/// The reset values of the SX1257 registers, from the register table of the
+/// data sheet, for use in `const` contexts. Registers that are documented
+/// only for the SX1255 are zero.
+pub const DEFAULT_REGISTERS_SX1257: [u8; 0x1B] = [
+    0x00,               // Mode: sleep.
+    0xC0, 0xE3, 0x8E,   // Receive frequency.
+    0xC0, 0xE3, 0x8E,   // Transmit frequency.
+    0x00,               // Version, not documented for SX1257.
+    0x2E,               // Transmit gain: DAC -3 dB, mixer -9.5 dB.
+    0x00, 0x00, 0x00,   // Not documented for SX1257.
+    0x3F,               // Highest LNA and baseband gain, 200 Ω input.
+    0xF5,               // ADC bandwidth over 400 KHz, 36 MHz trim, PGA 1000 KHz.
+    0x06,               // Receive PLL 300 KHz.
+    0x00,               // DIO mapping.
+    0x02,               // Clock output enabled.
+    0x00,               // Status.
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00,               // Low battery threshold.
+];
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+/// Which of the IC-version-specific registers hold something other than
+/// their defaults, from [HardRegisters::populated_version_specific]. These
+/// are the settings that [HardRegisters::serialize] drops when writing for
+/// the other IC.
+pub struct VersionSpecificUsage {
+    /// [HardRegisters::tx_frontend_1255] is set, SX1255 only.
+    pub tx_frontend_1255: bool,
+    /// [HardRegisters::iism] is set, SX1255 only.
+    pub iism: bool,
+    /// [HardRegisters::digital_bridge] is set, SX1255 only.
+    pub digital_bridge: bool,
+    /// [HardRegisters::low_battery_threshold] is set, SX1257 only.
+    pub low_battery_threshold: bool,
+}
+
+impl VersionSpecificUsage {
+    /// Any of the SX1255-only registers are set.
+    pub fn sx1255_only(&self) -> bool {
+        self.tx_frontend_1255 || self.iism || self.digital_bridge
+    }
+
+    /// Any of the SX1257-only registers are set.
+    pub fn sx1257_only(&self) -> bool {
+        self.low_battery_threshold
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+/// A register field holding a value the IC documents as illegal, from
+/// [HardRegisters::validate].
+pub struct ValidationError {
+    /// The address of the register holding the field.
+    pub register: u8,
+    /// The name of the field.
+    pub field: &'static str,
+    /// The offending value.
+    pub value: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A constraint on a register field, as data, for a user interface to show
+/// before anything is written. These are the rules that
+/// [HardRegisters::validate] enforces.
+pub struct ValidationRule {
+    /// The address of the register holding the field.
+    pub register: u8,
+    /// The name of the field.
+    pub field: &'static str,
+    /// The smallest value the field can hold.
+    pub min: u32,
+    /// The largest value the field can hold.
+    pub max: u32,
+    /// Values between `min` and `max` that are illegal.
+    pub forbidden: &'static [u32],
+    /// The IC the rule applies to, or `None` for both.
+    pub ic_version: Option<ICVersion>,
+    /// Other fields that the legal values depend upon, described for the
+    /// user, or `None` if the rule stands alone.
+    pub dependency: Option<&'static str>,
+}
+
+const VALIDATION_RULES: [ValidationRule; 7] = [
+    ValidationRule {
+        register: 0x0C,
+        field: "lna_gain",
+        min: 0,
+        max: 7,
+        forbidden: &[0, 7],
+        ic_version: None,
+        dependency: None,
+    },
+    ValidationRule {
+        register: 0x0B,
+        field: "dac_bw",
+        min: 0,
+        max: 7,
+        forbidden: &[6, 7],
+        ic_version: Some(ICVersion::SX1255),
+        dependency: Some("filter_bw: the FIR-DAC and analog filter must both pass the signal"),
+    },
+    ValidationRule {
+        register: 0x13,
+        field: "int_dec_m_parameter",
+        min: 0,
+        max: 1,
+        forbidden: &[],
+        ic_version: Some(ICVersion::SX1255),
+        dependency: None,
+    },
+    ValidationRule {
+        register: 0x13,
+        field: "int_dec_n_parameter",
+        min: 0,
+        max: 7,
+        forbidden: &[7],
+        ic_version: Some(ICVersion::SX1255),
+        dependency: None,
+    },
+    ValidationRule {
+        register: 0x13,
+        field: "iism_status",
+        min: 0,
+        max: 1,
+        forbidden: &[1],
+        ic_version: Some(ICVersion::SX1255),
+        dependency: Some("int_dec_mantissa, int_dec_m_parameter and int_dec_n_parameter"),
+    },
+    ValidationRule {
+        register: 0x10,
+        field: "dig_loopback_enable",
+        min: 0,
+        max: 1,
+        forbidden: &[],
+        ic_version: None,
+        dependency: Some("driver_enable: loop-back must be off while the power amplifier is on"),
+    },
+    ValidationRule {
+        register: 0x10,
+        field: "rf_loopback_enable",
+        min: 0,
+        max: 1,
+        forbidden: &[],
+        ic_version: None,
+        dependency: Some("driver_enable: loop-back must be off while the power amplifier is on"),
+    },
+];
+
+/// Every constraint that [HardRegisters::validate] checks.
+pub fn validation_rules() -> &'static [ValidationRule] {
+    &VALIDATION_RULES
+}
+
 impl HardRegisters {
+    /// Report which IC-version-specific registers differ from their
+    /// defaults, so that a caller can explain what would be lost by
+    /// writing this configuration to the other IC.
+    pub fn populated_version_specific(&self) -> VersionSpecificUsage {
+        VersionSpecificUsage {
+            tx_frontend_1255: self.tx_frontend_1255 != TxFrontend1255::default(),
+            iism: self.iism != IISM::default(),
+            digital_bridge: self.digital_bridge != DigitalBridge::default(),
+            low_battery_threshold: self.low_battery_threshold != LowBatteryThreshold::default(),
+        }
+    }
+
+    /// The fields holding values that the IC documents as illegal. The
+    /// SX1255-only registers are only checked for SX1255, since they are
+    /// never written to an SX1257.
+    pub fn validation_errors(
+        &self,
+        ic_version: ICVersion,
+    ) -> impl Iterator<Item = ValidationError> {
+        let sx1255 = ic_version == ICVersion::SX1255;
+        let lna_gain = self.rx_frontend.lna_gain;
+        let dac_bw = self.tx_frontend_1255.dac_bw;
+        let m = self.digital_bridge.int_dec_m_parameter;
+        let n = self.digital_bridge.int_dec_n_parameter;
+        let iism_status = self.digital_bridge.iism_status;
+        // Loop-back is for calibration and test, never with the power
+        // amplifier driving the antenna.
+        let dig_loopback = self.clock_select.dig_loopback_enable;
+        let rf_loopback = self.clock_select.rf_loopback_enable;
+        let driver_enable = self.mode.driver_enable;
+        let error = |register, field, value: u8, legal: bool| {
+            (!legal).then_some(ValidationError { register, field, value: value as u32 })
+        };
+        [
+            error(0x0C, "lna_gain", lna_gain, (1..=6).contains(&lna_gain)),
+            error(0x0B, "dac_bw", dac_bw, !sx1255 || dac_bw <= 5),
+            error(0x13, "int_dec_m_parameter", m, !sx1255 || m <= 1),
+            error(0x13, "int_dec_n_parameter", n, !sx1255 || n <= DigitalBridge::MAX_N),
+            error(0x13, "iism_status", iism_status as u8, !sx1255 || !iism_status),
+            error(0x10, "dig_loopback_enable", dig_loopback as u8, !dig_loopback || !driver_enable),
+            error(0x10, "rf_loopback_enable", rf_loopback as u8, !rf_loopback || !driver_enable),
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    #[cfg(feature = "alloc")]
+    /// Check every constrained field before the registers are written to the
+    /// IC, returning all of the illegal values found.
+    pub fn validate(&self, ic_version: ICVersion) -> Result<(), Vec<ValidationError>> {
+        let errors: Vec<ValidationError> = self.validation_errors(ic_version).collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// The reset state of the IC: [DEFAULT_REGISTERS_SX1255] or
+    /// [DEFAULT_REGISTERS_SX1257], decoded.
+    pub fn default_for(ic_version: ICVersion) -> HardRegisters {
+        let bytes = match ic_version {
+            ICVersion::SX1255 => &DEFAULT_REGISTERS_SX1255,
+            ICVersion::SX1257 => &DEFAULT_REGISTERS_SX1257,
+        };
+        Self::deserialize(bytes, ic_version).expect("the reset values decode")
+    }
+
     pub fn serialize(&self, bytes: &mut [u8; 0x1B], ic_version: ICVersion) {
         // There might be a more idiomatic way to do this with BinarySerdeBufSafe
         // and traits, but it probably would work on all fields, and it's
         // necessary to exclude some for different IC versions.
         const E : binary_serde::Endianness = Endianness::Big;
-        self.mode.binary_serialize(&mut bytes[0..=0], E);
-        self.rx.binary_serialize(&mut bytes[1..=3], E);
-        self.tx.binary_serialize(&mut bytes[4..=6], E);
-        self.version.binary_serialize(&mut bytes[7..=7], E);
-		self.tx_frontend.binary_serialize(&mut bytes[8..=9], E);
+        use RegisterAddr as R;
+        let span = RegisterAddr::span;
+        self.mode.binary_serialize(&mut bytes[span(R::Mode, R::Mode)], E);
+        self.rx.binary_serialize(&mut bytes[span(R::RxFreqMsb, R::RxFreqLsb)], E);
+        self.tx.binary_serialize(&mut bytes[span(R::TxFreqMsb, R::TxFreqLsb)], E);
+        self.version.binary_serialize(&mut bytes[span(R::Version, R::Version)], E);
+		self.tx_frontend.binary_serialize(&mut bytes[span(R::TxGain, R::TxGain)], E);
 
         if ic_version == ICVersion::SX1255 {
-		    self.tx_frontend_1255.binary_serialize(&mut bytes[0xA..=0xB], E);
+		    let tx_1255 = span(R::TxMixerTank, R::TxDacBw);
+		    self.tx_frontend_1255.binary_serialize(&mut bytes[tx_1255], E);
         }
         else {
-            bytes[0xA..0xB].fill(0);
+            bytes[span(R::TxMixerTank, R::TxDacBw)].fill(0);
         }
 
-        self.rx_frontend.binary_serialize(&mut bytes[0xC..=0xE], E);
-        self.io_map.binary_serialize(&mut bytes[0xF..=0xF], E);
-        self.clock_select.binary_serialize(&mut bytes[0x10..=0x10], E);
-        self.status.binary_serialize(&mut bytes[0x11..=0x11], E);
+        self.rx_frontend.binary_serialize(&mut bytes[span(R::RxGain, R::RxPllBw)], E);
+        self.io_map.binary_serialize(&mut bytes[span(R::DioMapping, R::DioMapping)], E);
+        self.clock_select.binary_serialize(&mut bytes[span(R::ClockSelect, R::ClockSelect)], E);
+        self.status.binary_serialize(&mut bytes[span(R::Status, R::Status)], E);
 
+        // Everything from IISM up to the low battery threshold belongs to
+        // one IC or the other, or is unused.
+        bytes[R::Iism.index()..R::LowBatteryThreshold.index()].fill(0);
         match ic_version {
             ICVersion::SX1257 => {
-                bytes[0x12..=0x19].fill(0);
-                self.low_battery_threshold.binary_serialize(&mut bytes[0x1A..=0x1A], E);
+                let threshold = span(R::LowBatteryThreshold, R::LowBatteryThreshold);
+                self.low_battery_threshold.binary_serialize(&mut bytes[threshold], E);
             }
             ICVersion::SX1255 => {
-                self.iism.binary_serialize(&mut bytes[0x12..=0x12], E);
-                self.digital_bridge.binary_serialize(&mut bytes[0x13..=0x13], E);
-                bytes[0x14..=0x1A].fill(0);
+                self.iism.binary_serialize(&mut bytes[span(R::Iism, R::Iism)], E);
+                let bridge = span(R::DigitalBridge, R::DigitalBridge);
+                self.digital_bridge.binary_serialize(&mut bytes[bridge], E);
+                bytes[R::LowBatteryThreshold.index()] = 0;
             }
         }
 	}
+
+    /// The writable registers that differ from `previous`, as address,
+    /// value pairs in ascending address order. If any byte of a frequency
+    /// changed, all three are included, so that the least significant byte
+    /// is written last and latches the new frequency.
+    #[cfg(feature = "alloc")]
+    pub fn diff(&self, previous: &HardRegisters, ic_version: ICVersion) -> Vec<(u8, u8)> {
+        self.diff_iter(previous, ic_version).collect()
+    }
+
+    /// The same pairs as [diff](Self::diff), without allocating.
+    pub fn diff_iter(
+        &self,
+        previous: &HardRegisters,
+        ic_version: ICVersion,
+    ) -> impl Iterator<Item = (u8, u8)> + use<> {
+        let mut new = [0u8; 0x1B];
+        let mut old = [0u8; 0x1B];
+        self.serialize(&mut new, ic_version);
+        previous.serialize(&mut old, ic_version);
+        RegisterAddr::ALL
+            .into_iter()
+            .filter(move |register| register.writable(ic_version))
+            .filter(move |register| {
+                let group = register.latch_group();
+                new[group.clone()] != old[group]
+            })
+            .map(move |register| (register as u8, new[register.index()]))
+    }
+
+    /// Encode the writable registers that differ from `previous` as a
+    /// packet of address, value byte pairs, in ascending address order so
+    /// that each frequency latches on its last byte. This is for slow links,
+    /// such as a UART to SPI bridge, where writing everything is wasteful.
+    #[cfg(feature = "alloc")]
+    pub fn delta_packet(&self, previous: &HardRegisters, ic_version: ICVersion) -> Vec<u8> {
+        self.diff_iter(previous, ic_version).flat_map(|(addr, value)| [addr, value]).collect()
+    }
+
+    /// Apply a packet from [delta_packet](Self::delta_packet). Nothing is
+    /// changed if the packet is malformed, names a register that isn't
+    /// writable on `ic_version`, or writes a code that
+    /// [deserialize](Self::deserialize) rejects. A register that only the
+    /// other IC has fails with [Error::UnsupportedOnIcVersion]. The settings
+    /// that only the other IC has are kept.
+    pub fn apply_delta_packet(
+        &mut self,
+        packet: &[u8],
+        ic_version: ICVersion,
+    ) -> Result<(), Error> {
+        if packet.len() % 2 != 0 {
+            return Err(Error::InvalidRegisterValue("delta packet"));
+        }
+        let mut bytes = [0u8; 0x1B];
+        self.serialize(&mut bytes, ic_version);
+        for pair in packet.chunks_exact(2) {
+            match RegisterAddr::from_addr(pair[0]) {
+                Some(register) if register.writable(ic_version) => {
+                    bytes[register.index()] = pair[1];
+                }
+                Some(register) if register.writable(ICVersion::SX1255)
+                    || register.writable(ICVersion::SX1257) => {
+                    return Err(Error::UnsupportedOnIcVersion(register.name()));
+                }
+                _ => return Err(Error::InvalidRegisterValue("delta packet")),
+            }
+        }
+        let mut applied = Self::deserialize(&bytes, ic_version)?;
+        match ic_version {
+            ICVersion::SX1255 => {
+                applied.low_battery_threshold = self.low_battery_threshold.clone();
+            }
+            ICVersion::SX1257 => {
+                applied.tx_frontend_1255 = self.tx_frontend_1255.clone();
+                applied.iism = self.iism.clone();
+                applied.digital_bridge = self.digital_bridge.clone();
+            }
+        }
+        *self = applied;
+        Ok(())
+    }
+
+    /// Decode an SPI capture exported by a logic analyzer as CSV, one
+    /// address,data pair per line, with the address byte as it appeared on
+    /// the bus. Numbers may be hexadecimal with a `0x` prefix, or decimal. A
+    /// header line is skipped. Writes are applied in order to the reset state
+    /// from [default_for](Self::default_for), reads are ignored.
+    ///
+    /// The frequency registers are honored the way the IC latches them: the
+    /// upper two bytes are held until the least significant byte is written.
+    /// `oscillator` is in Hz, and is used to report latched frequencies.
+    ///
+    /// Returns the resulting registers and a log of what each write did.
+    #[cfg(feature = "alloc")]
+    pub fn from_spi_csv(csv: &str, ic_version: ICVersion, oscillator: f64)
+     -> Result<(HardRegisters, Vec<String>), Error> {
+        fn number(text: &str) -> Option<u8> {
+            let text = text.trim();
+            match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+                Some(hex) => u8::from_str_radix(hex, 16).ok(),
+                None => text.parse().ok(),
+            }
+        }
+
+        let mut bytes = [0u8; 0x1B];
+        Self::default_for(ic_version).serialize(&mut bytes, ic_version);
+        let mut held = bytes;
+        let mut log = Vec::new();
+
+        for (index, line) in csv.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut fields = line.split(',');
+            let parsed = match (fields.next().and_then(number), fields.next().and_then(number)) {
+                (Some(address), Some(data)) => (address, data),
+                _ if index == 0 => continue,
+                _ => return Err(Error::InvalidCsv(index + 1)),
+            };
+            let (address, data) = parsed;
+            if address & 0x80 == 0 {
+                continue;
+            }
+            let address = (address & 0x7F) as usize;
+            if address >= bytes.len() {
+                return Err(Error::InvalidCsv(index + 1));
+            }
+
+            use RegisterAddr as R;
+            match RegisterAddr::from_addr(address as u8) {
+                Some(R::RxFreqMsb | R::RxFreqMid | R::TxFreqMsb | R::TxFreqMid) => {
+                    held[address] = data;
+                    log.push(format!("0x{address:02X} <- 0x{data:02X}, held until the LSB is written"));
+                }
+                Some(R::RxFreqLsb | R::TxFreqLsb) => {
+                    let first = address - 2;
+                    held[address] = data;
+                    bytes[first..=address].copy_from_slice(&held[first..=address]);
+                    let raw = u32::from_be_bytes([0, bytes[first], bytes[first + 1], data]);
+                    let hz = Frequency::from_raw(raw).to_hz(oscillator, ic_version);
+                    let path = if address == R::RxFreqLsb.index() { "RX" } else { "TX" };
+                    log.push(format!(
+                        "0x{address:02X} <- 0x{data:02X}, {path} frequency latched at {:.6} MHz",
+                        hz / 1e6
+                    ));
+                }
+                _ => {
+                    bytes[address] = data;
+                    log.push(format!("0x{address:02X} <- 0x{data:02X}"));
+                }
+            }
+        }
+        Ok((Self::deserialize(&bytes, ic_version)?, log))
+    }
+
+    /// A labeled, multi-line summary of the registers, for dumping to a
+    /// terminal during bring-up. `crystal_frequency` is in Hz. The registers
+    /// that aren't documented for `ic_version` are left out.
+    #[cfg(feature = "alloc")]
+    pub fn report(&self, crystal_frequency: f64, ic_version: ICVersion) -> String {
+        let mode = &self.mode;
+        let mode_name = match (mode.standby_enable, mode.rx_enable, mode.tx_enable) {
+            (_, true, true) => "full duplex",
+            (_, true, false) => "receive",
+            (_, false, true) => "transmit",
+            (true, false, false) => "standby",
+            (false, false, false) => "sleep",
+        };
+        let pa = if mode.driver_enable { ", PA on" } else { "" };
+        let rx = &self.rx_frontend;
+        let tx = &self.tx_frontend;
+        let status = &self.status;
+
+        let mut lines = Vec::new();
+        lines.push(format!("Mode: {mode_name}{pa}"));
+        lines.push(format!(
+            "RX frequency: {:.6} MHz",
+            self.rx.to_hz(crystal_frequency, ic_version) / 1e6
+        ));
+        lines.push(format!(
+            "TX frequency: {:.6} MHz",
+            self.tx.to_hz(crystal_frequency, ic_version) / 1e6
+        ));
+        lines.push(format!("RX LNA gain: {} dB", rx.lna_gain_db()));
+        lines.push(format!("RX baseband gain: {} dB", rx.baseband_gain_db()));
+        lines.push(format!("RX ADC bandwidth: {} KHz and up", rx.adc_bw.min_khz()));
+        lines.push(format!("RX PGA bandwidth: {} KHz", rx.pga_bw.khz()));
+        lines.push(format!("RX PLL bandwidth: {} KHz", (rx.pll_bw as u16 + 1) * 75));
+        lines.push(format!("TX DAC gain: {} dB", TxFrontend::dac_gain_from_code(tx.dac_gain)));
+        lines.push(format!(
+            "TX mixer gain: {} dB",
+            TxFrontend::mixer_gain_from_code(tx.mixer_gain)
+        ));
+        match ic_version {
+            ICVersion::SX1255 => {
+                let tx = &self.tx_frontend_1255;
+                lines.push(format!("TX mixer tank: {} fF", tx.mixer_tank_cap as u16 * 128));
+                lines.push(format!(
+                    "TX mixer tank resistance: {} Ω",
+                    tx.mixer_tank_resistance.ohms()
+                ));
+                lines.push(format!("TX PLL bandwidth: {} KHz", (tx.pll_bw as u16 + 1) * 75));
+                lines.push(format!("TX analog filter: {:.3} MHz", tx.filter_bw_mhz()));
+                lines.push(format!("TX FIR-DAC taps: {}", tx.tap_count()));
+                lines.push(format!(
+                    "I²S mode: {:?}, CLK_OUT divisor {}",
+                    self.iism.mode,
+                    self.iism.clock_div.divisor().unwrap_or(1)
+                ));
+                match self.digital_bridge.factor() {
+                    Some(factor) => lines.push(format!("Digital bridge factor: {factor}")),
+                    None => lines.push(String::from("Digital bridge factor: invalid")),
+                }
+            }
+            ICVersion::SX1257 => {
+                lines.push(format!(
+                    "Low battery threshold: {} V",
+                    self.low_battery_threshold.threshold.volts()
+                ));
+            }
+        }
+        lines.push(format!(
+            "Status: oscillator {}, RX PLL {}, TX PLL {}, battery {}",
+            if status.xosc_ready { "ready" } else { "not ready" },
+            if status.pll_lock_rx { "locked" } else { "unlocked" },
+            if status.pll_lock_tx { "locked" } else { "unlocked" },
+            if status.eol { "low" } else { "ok" },
+        ));
+        lines.join("\n")
+    }
+
+    /// The inverse of [serialize](Self::serialize), for a register image
+    /// read back from the IC. Registers that aren't documented for
+    /// `ic_version` are left at their defaults. A register holding a code
+    /// that none of its enumerations describe fails with
+    /// [Error::InvalidRegisterValue] naming the field, rather than decoding
+    /// to something the IC isn't doing.
+    pub fn deserialize(bytes: &[u8; 0x1B], ic_version: ICVersion) -> Result<HardRegisters, Error> {
+        fn field<T: BinarySerde>(bytes: &[u8], name: &'static str) -> Result<T, Error> {
+            T::binary_deserialize(bytes, Endianness::Big)
+                .map_err(|_| Error::InvalidRegisterValue(name))
+        }
+
+        use RegisterAddr as R;
+        let span = move |first, last| &bytes[RegisterAddr::span(first, last)];
+
+        let mut registers = HardRegisters {
+            mode: field(span(R::Mode, R::Mode), "mode")?,
+            rx: field(span(R::RxFreqMsb, R::RxFreqLsb), "rx")?,
+            tx: field(span(R::TxFreqMsb, R::TxFreqLsb), "tx")?,
+            version: field(span(R::Version, R::Version), "version")?,
+            tx_frontend: field(span(R::TxGain, R::TxGain), "tx_frontend")?,
+            rx_frontend: field(span(R::RxGain, R::RxPllBw), "rx_frontend")?,
+            io_map: field(span(R::DioMapping, R::DioMapping), "io_map")?,
+            clock_select: field(span(R::ClockSelect, R::ClockSelect), "clock_select")?,
+            status: field(span(R::Status, R::Status), "status")?,
+            ..Default::default()
+        };
+
+        match ic_version {
+            ICVersion::SX1257 => {
+                registers.low_battery_threshold = field(
+                    span(R::LowBatteryThreshold, R::LowBatteryThreshold),
+                    "low_battery_threshold",
+                )?;
+            }
+            ICVersion::SX1255 => {
+                registers.tx_frontend_1255 =
+                    field(span(R::TxMixerTank, R::TxDacBw), "tx_frontend_1255")?;
+                registers.iism = field(span(R::Iism, R::Iism), "iism")?;
+                registers.digital_bridge =
+                    field(span(R::DigitalBridge, R::DigitalBridge), "digital_bridge")?;
+            }
+        }
+        Ok(registers)
+    }
 }
 
 
@@ -681,3 +2384,1204 @@ fn _stub() {
     let mut data: [u8; 0x1B] = [0; 0x1B];
 	reg.serialize(&mut data, ICVersion::SX1255);
 }
+
+/// Named register configurations, computed once and kept for switching
+/// between operating modes, such as receive, transmit and scan presets. A
+/// preset is written to the IC by passing [get](Self::get) to
+/// [write_all](crate::transport::Sx125x::write_all).
+#[cfg(feature = "alloc")]
+#[derive(Debug, Default, Clone)]
+pub struct PresetTable {
+    presets: BTreeMap<String, HardRegisters>,
+}
+
+#[cfg(feature = "alloc")]
+impl PresetTable {
+    /// An empty table.
+    pub fn new() -> PresetTable {
+        PresetTable::default()
+    }
+
+    /// Store `registers` as `name`, replacing any preset of that name.
+    pub fn insert(&mut self, name: &str, registers: HardRegisters) {
+        self.presets.insert(String::from(name), registers);
+    }
+
+    /// The preset called `name`.
+    pub fn get(&self, name: &str) -> Option<&HardRegisters> {
+        self.presets.get(name)
+    }
+
+    /// Remove and return the preset called `name`.
+    pub fn remove(&mut self, name: &str) -> Option<HardRegisters> {
+        self.presets.remove(name)
+    }
+
+    /// The preset names, in sorted order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.presets.keys().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frequency_from_raw_masks_high_byte() {
+        assert_eq!(Frequency::from_raw(0xFF_00_00_00).raw(), 0);
+        assert_eq!(Frequency::from_raw(0xFF_C0_E3_8E).raw(), 0xC0_E3_8E);
+        assert_eq!(Frequency::from_raw(Frequency::MAX).raw(), Frequency::MAX);
+    }
+
+    #[test]
+    fn analog_filter_narrower_than_fir_is_flagged() {
+        let narrow = TxFrontend1255 { filter_bw: 0, dac_bw: 1, ..Default::default() };
+        assert_eq!(
+            narrow.joint_bandwidth_ok(100.0),
+            Err(Error::InvalidRegisterValue("filter_bw"))
+        );
+
+        let wide = TxFrontend1255 { filter_bw: 31, dac_bw: 5, ..Default::default() };
+        assert_eq!(wide.joint_bandwidth_ok(200.0), Ok(()));
+        assert_eq!(
+            wide.joint_bandwidth_ok(280.0),
+            Err(Error::InvalidRegisterValue("dac_bw"))
+        );
+    }
+
+    #[test]
+    fn tighter_acpr_needs_more_taps() {
+        let loose = TxFrontend1255::taps_for_acpr(100.0, 1000.0, 20.0);
+        let tight = TxFrontend1255::taps_for_acpr(100.0, 1000.0, 60.0);
+        assert_eq!(loose, 1);
+        assert_eq!(tight, 4);
+        // A signal wider than the narrowest FIR limits the choice.
+        assert_eq!(TxFrontend1255::taps_for_acpr(400.0, 1000.0, 100.0), 2);
+    }
+
+    #[test]
+    fn invalid_gain_set_changes_nothing() {
+        let mut rx = RxFrontend { lna_gain: 3, baseband_gain: 4, ..Default::default() };
+        let result = rx.set_gains(GainSet { lna_db: 0.0, baseband_db: 40.0 });
+        assert_eq!(result, Err(Error::InvalidRegisterValue("baseband_gain")));
+        assert_eq!(rx.lna_gain, 3);
+        assert_eq!(rx.baseband_gain, 4);
+
+        rx.set_gains(GainSet { lna_db: -24.0, baseband_db: 6.0 }).unwrap();
+        assert_eq!(rx.lna_gain, 4);
+        assert_eq!(rx.baseband_gain, 15);
+
+        let mut tx = TxFrontend { dac_gain: 1, mixer_gain: 2, ..Default::default() };
+        let result = tx.set_gains(TxGainSet { dac_db: 3.0, mixer_db: -20.0 });
+        assert_eq!(result, Err(Error::InvalidRegisterValue("dac_gain")));
+        assert_eq!(tx.mixer_gain, 2);
+
+        tx.set_gains(TxGainSet { dac_db: 0.0, mixer_db: -7.5 }).unwrap();
+        assert_eq!(tx.dac_gain, 3);
+        assert_eq!(tx.mixer_gain, 15);
+    }
+
+    #[test]
+    fn defaults_are_the_data_sheet_reset_values() {
+        for ic in [ICVersion::SX1255, ICVersion::SX1257] {
+            let regs = HardRegisters::default_for(ic);
+            assert_eq!(regs.mode, Mode::default());
+            assert_eq!(regs.rx, Frequency::from_raw(0xC0E38E));
+            assert_eq!(regs.tx, Frequency::from_raw(0xC0E38E));
+            assert_eq!(regs.tx_frontend.dac_gain_db(), -3.0);
+            assert_eq!(regs.tx_frontend.mixer_gain_db(), -9.5);
+            assert_eq!(regs.rx_frontend.lna(), RxLnaGain::G0dB);
+            assert_eq!(regs.rx_frontend.baseband_gain, 15);
+            assert_eq!(regs.rx_frontend.zin, RxZIn::I200Ω);
+            assert_eq!(regs.rx_frontend.adc_bw, RxADCBw::BWOver400KHz);
+            assert_eq!(regs.rx_frontend.adc_trim, RxADCTrim::XTal36MHz);
+            assert_eq!(regs.rx_frontend.pga_bw, RxPGABw::BW1000KHz);
+            assert_eq!(regs.rx_frontend.pll_bw, 3);
+            assert!(regs.clock_select.clock_output_enable);
+            assert!(!regs.clock_select.rf_loopback_enable);
+
+            let mut bytes = [0u8; 0x1B];
+            regs.serialize(&mut bytes, ic);
+            let defaults = match ic {
+                ICVersion::SX1255 => DEFAULT_REGISTERS_SX1255,
+                ICVersion::SX1257 => DEFAULT_REGISTERS_SX1257,
+            };
+            assert_eq!(bytes, defaults);
+        }
+
+        let regs = HardRegisters::default_for(ICVersion::SX1255);
+        assert_eq!(regs.version.detect_ic(), Some(ICVersion::SX1255));
+        assert_eq!(regs.tx_frontend_1255.pll_bw, 3);
+        assert_eq!(regs.tx_frontend_1255.tap_count(), 40);
+    }
+
+    #[test]
+    fn sx1255_tuning_range_covers_434_mhz() {
+        let (min, max, step) = Frequency::tuning_range(36e6, ICVersion::SX1255);
+        assert!((step - 34.3323).abs() < 1e-4);
+        assert!(min >= 400e6 && min < 400e6 + step);
+        assert!(max <= 512e6 && max > 512e6 - step);
+        assert!(min < 434e6 && 434e6 < max);
+    }
+
+    /// Registers with every field moved away from its default.
+    fn sample_registers() -> HardRegisters {
+        HardRegisters {
+            mode: Mode { rx_enable: true, standby_enable: true, ..Default::default() },
+            rx: Frequency::from_raw(0xC0E38E),
+            tx: Frequency::from_raw(0x123456),
+            version: Version { fill_revision_number: 1, metal_mask_revision_number: 0xA },
+            tx_frontend: TxFrontend { dac_gain: 2, mixer_gain: 9, ..Default::default() },
+            tx_frontend_1255: TxFrontend1255 {
+                mixer_tank_cap: 3,
+                mixer_tank_resistance: TxMixerTankResistance::Ω2180,
+                pll_bw: 2,
+                filter_bw: 17,
+                dac_bw: 4,
+                ..Default::default()
+            },
+            rx_frontend: RxFrontend {
+                lna_gain: 2,
+                baseband_gain: 11,
+                zin: RxZIn::I200Ω,
+                adc_bw: RxADCBw::BW200To400KHz,
+                adc_trim: RxADCTrim::XTal32Mhz,
+                pga_bw: RxPGABw::BW750KHz,
+                pll_bw: 1,
+                adc_temp: true,
+                ..Default::default()
+            },
+            io_map: IOMap { iomap0: IOMap0::Eol, ..Default::default() },
+            clock_select: ClockSelect {
+                rf_loopback_enable: true,
+                clock_output_enable: true,
+                clock_select_tx_dac: ClockSelectTxDAC::External,
+                ..Default::default()
+            },
+            status: Status { xosc_ready: true, pll_lock_rx: true, ..Default::default() },
+            iism: IISM {
+                tx_during_rx_disable: true,
+                mode: IISMMode::B2,
+                clock_div: IISMClockDiv::D8,
+                ..Default::default()
+            },
+            digital_bridge: DigitalBridge {
+                int_dec_mantissa: IntDecMantissa::M9,
+                int_dec_m_parameter: 1,
+                int_dec_n_parameter: 5,
+                iism_truncation: IISMTruncation::LSB,
+                ..Default::default()
+            },
+            low_battery_threshold: LowBatteryThreshold {
+                threshold: ThresholdValue::V2_935,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn deserialize_round_trips_sx1255() {
+        let mut bytes = [0u8; 0x1B];
+        sample_registers().serialize(&mut bytes, ICVersion::SX1255);
+        let mut expected = sample_registers();
+        expected.low_battery_threshold = LowBatteryThreshold::default();
+        assert_eq!(HardRegisters::deserialize(&bytes, ICVersion::SX1255), Ok(expected));
+    }
+
+    #[test]
+    fn deserialize_round_trips_sx1257() {
+        let mut bytes = [0u8; 0x1B];
+        sample_registers().serialize(&mut bytes, ICVersion::SX1257);
+        let mut expected = sample_registers();
+        expected.tx_frontend_1255 = TxFrontend1255::default();
+        expected.iism = IISM::default();
+        expected.digital_bridge = DigitalBridge::default();
+        assert_eq!(HardRegisters::deserialize(&bytes, ICVersion::SX1257), Ok(expected));
+    }
+
+    #[test]
+    fn deserialize_rejects_undocumented_codes() {
+        let mut bytes = DEFAULT_REGISTERS_SX1255;
+        // ADC bandwidth code 3.
+        bytes[RegisterAddr::RxBw.index()] = 0x74;
+        assert_eq!(
+            HardRegisters::deserialize(&bytes, ICVersion::SX1255),
+            Err(Error::InvalidRegisterValue("rx_frontend"))
+        );
+    }
+
+    #[test]
+    fn version_specific_usage() {
+        let mut registers = HardRegisters::default();
+        assert_eq!(registers.populated_version_specific(), VersionSpecificUsage::default());
+
+        registers.iism.mode = IISMMode::B1;
+        let usage = registers.populated_version_specific();
+        assert!(usage.iism);
+        assert!(usage.sx1255_only());
+        assert!(!usage.sx1257_only());
+    }
+
+    #[test]
+    fn default_frequency_round_trips() {
+        let step = Frequency::step_hz(36e6, ICVersion::SX1255);
+        let default = Frequency::from_raw(0xC0E38E);
+        let hz = default.to_hz(36e6, ICVersion::SX1255);
+        assert!((434e6 - hz) >= 0.0 && (434e6 - hz) < step);
+        assert_eq!(Frequency::from_hz(434e6, 36e6, ICVersion::SX1255), default);
+        assert_eq!(Frequency::from_hz(868e6, 36e6, ICVersion::SX1257), default);
+    }
+
+    #[test]
+    fn from_hz_saturates() {
+        assert_eq!(Frequency::from_hz(1e12, 36e6, ICVersion::SX1255).raw(), Frequency::MAX);
+        assert_eq!(Frequency::from_hz(-1.0, 36e6, ICVersion::SX1255).raw(), 0);
+    }
+
+    #[test]
+    fn narrow_channel_gets_narrowest_adc_bandwidth() {
+        let sx1255 = ICVersion::SX1255;
+        let sx1257 = ICVersion::SX1257;
+        assert_eq!(RxADCBw::optimal_for_channel(50, sx1255), RxADCBw::BW100To400KHzSX1255);
+        assert_eq!(RxADCBw::optimal_for_channel(50, sx1257), RxADCBw::BW100To400KHz);
+        assert_eq!(RxADCBw::optimal_for_channel(250, sx1255), RxADCBw::BW200To400KHz);
+        assert_eq!(RxADCBw::optimal_for_channel(1000, sx1257), RxADCBw::BWOver400KHz);
+        assert_eq!(RxADCBw::BW100To400KHzSX1255.min_khz(), 100);
+    }
+
+    #[test]
+    fn spi_csv_capture_decodes() {
+        let csv = "address,data\n0x81,0xC0\n0x82,0xE3\n0x83,0x8E\n0x0C,0x00\n0x8C,0x5E\n0x84,0x11\n";
+        let (registers, log) = HardRegisters::from_spi_csv(csv, ICVersion::SX1255, 36e6).unwrap();
+        assert_eq!(registers.rx, Frequency::from_raw(0xC0E38E));
+        assert_eq!(registers.rx_frontend.lna_gain, 2);
+        assert_eq!(registers.rx_frontend.baseband_gain, 15);
+        // The TX MSB was never latched by an LSB write.
+        assert_eq!(registers.tx, Frequency::from_raw(0xC0E38E));
+        assert_eq!(log.len(), 5);
+        assert!(log[2].contains("RX frequency latched at 433.999992 MHz"));
+
+        assert_eq!(
+            HardRegisters::from_spi_csv("0x81,0xC0\n0x82,zz\n", ICVersion::SX1255, 36e6),
+            Err(Error::InvalidCsv(2))
+        );
+    }
+
+    #[test]
+    fn larger_hops_are_slower() {
+        assert_eq!(Frequency::hop_time_s(100e3), 20e-6);
+        assert!((Frequency::hop_time_s(1.2e6) - 30e-6).abs() < 1e-12);
+        assert_eq!(Frequency::hop_time_s(100e6), 50e-6);
+
+        let small = max_hop_rate_hz(100e3, 10e6);
+        let large = max_hop_rate_hz(10e6, 10e6);
+        assert!(large < small);
+        // 20µs settling plus 4.8µs of SPI.
+        assert!((small - 1.0 / 24.8e-6).abs() < 1.0);
+    }
+
+    /// Every input a dB conversion is likely to see, including the ones it
+    /// shouldn't.
+    fn db_domain() -> impl Iterator<Item = f32> {
+        (-4000..=4000)
+            .map(|i| i as f32 * 0.025)
+            .chain([f32::NAN, f32::INFINITY, f32::NEG_INFINITY, f32::MAX, f32::MIN])
+    }
+
+    #[test]
+    fn conversions_never_produce_reserved_codes() {
+        for db in db_domain() {
+            assert!((1..=6).contains(&RxFrontend::lna_gain_code(db)), "LNA {db}");
+            assert!(RxFrontend::baseband_gain_code(db) <= 15, "baseband {db}");
+            // The high bit of the DAC gain is the test Vref.
+            assert!(TxFrontend::dac_gain_code(db) <= 3, "DAC {db}");
+            assert!(TxFrontend::mixer_gain_code(db) <= 15, "mixer {db}");
+        }
+        for khz in 0..=u16::MAX {
+            let code = RxADCBw::optimal_for_channel(khz, ICVersion::SX1255) as u8;
+            assert!([1, 5, 7].contains(&code), "SX1255 ADC bandwidth {khz}");
+            let code = RxADCBw::optimal_for_channel(khz, ICVersion::SX1257) as u8;
+            assert!([2, 5, 7].contains(&code), "SX1257 ADC bandwidth {khz}");
+        }
+        for signal in (0..=1000).step_by(10) {
+            for acpr in (0..=120).step_by(5) {
+                let dac_bw = TxFrontend1255::taps_for_acpr(signal as f32, 1000.0, acpr as f32);
+                assert!(dac_bw <= 5, "FIR taps beyond 64");
+            }
+        }
+    }
+
+    #[test]
+    fn narrower_bandwidth_improves_sensitivity() {
+        let wide = RxFrontend {
+            lna_gain: 1,
+            pga_bw: RxPGABw::BW1500KHz,
+            ..Default::default()
+        };
+        let narrow = RxFrontend {
+            lna_gain: 1,
+            pga_bw: RxPGABw::BW500KHz,
+            ..Default::default()
+        };
+        assert!(narrow.sensitivity_dbm(10.0) < wide.sensitivity_dbm(10.0));
+        // -174 + 4.5 + 60 + 10
+        assert!((narrow.sensitivity_dbm(10.0) - -99.5).abs() < 0.01);
+
+        let attenuated = RxFrontend {
+            lna_gain: 6,
+            pga_bw: RxPGABw::BW500KHz,
+            ..Default::default()
+        };
+        assert!(attenuated.sensitivity_dbm(10.0) > narrow.sensitivity_dbm(10.0));
+    }
+
+    #[test]
+    fn digital_bridge_factors() {
+        let bridge = DigitalBridge::from_factor(8).unwrap();
+        assert_eq!(bridge.int_dec_mantissa, IntDecMantissa::M8);
+        assert_eq!(bridge.int_dec_m_parameter, 0);
+        assert_eq!(bridge.int_dec_n_parameter, 0);
+
+        let bridge = DigitalBridge::from_factor(72).unwrap();
+        assert_eq!(bridge.int_dec_mantissa, IntDecMantissa::M9);
+        assert_eq!(bridge.int_dec_m_parameter, 0);
+        assert_eq!(bridge.int_dec_n_parameter, 3);
+        assert_eq!(bridge.factor(), Some(72));
+
+        let bridge = DigitalBridge::from_factor(27 * 64).unwrap();
+        assert_eq!(bridge.int_dec_m_parameter, 1);
+        assert_eq!(bridge.int_dec_n_parameter, 6);
+
+        assert!(DigitalBridge::from_factor(10).is_none());
+        assert!(DigitalBridge::from_factor(9 * 128).is_none());
+        assert_eq!(DigitalBridge::nearest_factor(10).factor(), Some(9));
+
+        let mut bridge = DigitalBridge::default();
+        bridge.int_dec_n_parameter = 7;
+        assert_eq!(bridge.factor(), None);
+    }
+
+    #[test]
+    fn transmit_oversampling_of_32() {
+        let bridge = DigitalBridge::for_transmit_oversampling(32).unwrap();
+        assert_eq!(bridge.factor(), Some(32));
+        assert_eq!(bridge.int_dec_mantissa, IntDecMantissa::M8);
+        assert_eq!(bridge.int_dec_n_parameter, 2);
+        assert!(!bridge.iism_status);
+        assert!(DigitalBridge::for_transmit_oversampling(33).is_none());
+
+        let mut regs = HardRegisters::default_for(ICVersion::SX1255);
+        regs.digital_bridge = bridge;
+        assert_eq!(regs.validate(ICVersion::SX1255), Ok(()));
+    }
+
+    #[test]
+    fn narrowest_adc_bandwidth_has_highest_enob() {
+        let enob = [RxADCBw::BWOver400KHz, RxADCBw::BW200To400KHz, RxADCBw::BW100To400KHz]
+            .map(|adc_bw| RxFrontend { adc_bw, ..Default::default() }.enob());
+        assert!(enob[0] < enob[1] && enob[1] < enob[2]);
+        assert!((enob[0] - (60.0 - 1.76) / 6.02).abs() < 1e-4);
+    }
+
+    #[test]
+    fn higher_temperature_reading_is_colder() {
+        // Calibrated as 25 °C reading 100.
+        let offset = 25.0 + 100.0;
+        assert_eq!(RxFrontend::temperature_celsius(100, offset), 25.0);
+        assert_eq!(RxFrontend::temperature_celsius(110, offset), 15.0);
+        assert!(
+            RxFrontend::temperature_celsius(101, offset)
+                < RxFrontend::temperature_celsius(100, offset)
+        );
+    }
+
+    #[test]
+    fn gain_db_helpers() {
+        let mut rx = RxFrontend::default();
+        rx.set_lna_gain_db(0.0);
+        assert_eq!(rx.lna_gain, 1);
+        rx.set_lna_gain_db(-48.0);
+        assert_eq!(rx.lna_gain, 6);
+        rx.set_lna_gain_db(-100.0);
+        assert_eq!(rx.lna_gain, 6);
+        rx.set_lna_gain_db(10.0);
+        assert_eq!(rx.lna_gain, 1);
+        rx.set_lna_gain_db(-20.0);
+        assert_eq!(rx.lna_gain_db(), -24.0);
+
+        rx.set_baseband_gain_db(-24.0);
+        assert_eq!(rx.baseband_gain, 0);
+        rx.set_baseband_gain_db(6.0);
+        assert_eq!(rx.baseband_gain, 15);
+        rx.set_baseband_gain_db(20.0);
+        assert_eq!(rx.baseband_gain, 15);
+        rx.set_baseband_gain_db(-9.2);
+        assert_eq!(rx.baseband_gain_db(), -10.0);
+    }
+
+    #[test]
+    fn clk_out_follows_the_measured_oscillator() {
+        // A 36 MHz crystal measured 450 Hz high.
+        let oscillator = 36_000_450.0;
+        let iism = IISM { clock_div: IISMClockDiv::D12, ..Default::default() };
+        assert_eq!(iism.clk_out_hz(oscillator), 3_000_037.5);
+        let iism = IISM { clock_div: IISMClockDiv::D0, ..Default::default() };
+        assert_eq!(iism.clk_out_hz(oscillator), oscillator);
+    }
+
+    #[test]
+    fn temperature_is_invariant_across_adc_trim() {
+        let offset = 25.0 + 100.0;
+        let trims = [RxADCTrim::XTal32Mhz, RxADCTrim::XTal36MHz];
+        for raw in [90, 100, 140] {
+            let readings = trims.map(|adc_trim| {
+                let rx = RxFrontend { adc_trim, adc_temp: true, ..Default::default() };
+                rx.measure_temperature(raw, offset)
+            });
+            assert_eq!(readings[0], readings[1]);
+            assert_eq!(readings[0], RxFrontend::temperature_celsius(raw, offset));
+        }
+    }
+
+    #[test]
+    fn validation_rules_describe_validate() {
+        let lna = validation_rules().iter().find(|rule| rule.field == "lna_gain").unwrap();
+        assert_eq!(lna.register, 0x0C);
+        assert_eq!(lna.forbidden, &[0, 7]);
+        assert_eq!(lna.ic_version, None);
+
+        // Each forbidden value is one that validate rejects.
+        for rule in validation_rules() {
+            for value in rule.forbidden {
+                let mut regs = HardRegisters::default_for(ICVersion::SX1255);
+                let value = *value as u8;
+                match rule.field {
+                    "lna_gain" => regs.rx_frontend.lna_gain = value,
+                    "dac_bw" => regs.tx_frontend_1255.dac_bw = value,
+                    "int_dec_n_parameter" => regs.digital_bridge.int_dec_n_parameter = value,
+                    "iism_status" => regs.digital_bridge.iism_status = value != 0,
+                    field => panic!("no test for {field}"),
+                }
+                let error = only_error(&regs, ICVersion::SX1255);
+                assert_eq!((error.register, error.field), (rule.register, rule.field));
+            }
+        }
+
+        // Dependent errors report a value within the range of their rule.
+        let mut regs = HardRegisters::default_for(ICVersion::SX1255);
+        regs.mode.driver_enable = true;
+        regs.clock_select.dig_loopback_enable = true;
+        regs.clock_select.rf_loopback_enable = true;
+        let errors: Vec<ValidationError> = regs.validation_errors(ICVersion::SX1255).collect();
+        assert_eq!(errors.len(), 2);
+        for error in errors {
+            let rule = validation_rules().iter().find(|rule| rule.field == error.field).unwrap();
+            assert_eq!(error.register, rule.register);
+            assert!((rule.min..=rule.max).contains(&error.value));
+        }
+    }
+
+    #[test]
+    fn clock_divisors() {
+        assert_eq!(IISMClockDiv::D0.divisor(), None);
+        assert_eq!(IISMClockDiv::D12.divisor(), Some(12));
+        assert_eq!(IISMClockDiv::D64.divisor(), Some(64));
+
+        assert_eq!(IISMClockDiv::nearest(0), IISMClockDiv::D0);
+        assert_eq!(IISMClockDiv::nearest(1), IISMClockDiv::D0);
+        assert_eq!(IISMClockDiv::nearest(3), IISMClockDiv::D2);
+        assert_eq!(IISMClockDiv::nearest(11), IISMClockDiv::D12);
+        assert_eq!(IISMClockDiv::nearest(20), IISMClockDiv::D16);
+        assert_eq!(IISMClockDiv::nearest(48), IISMClockDiv::D32);
+        assert_eq!(IISMClockDiv::nearest(1000), IISMClockDiv::D64);
+    }
+
+    #[test]
+    fn mode_b_ws_leads_by_one_period() {
+        for mode in [IISMMode::B1, IISMMode::B2] {
+            let iism = IISM { mode, ..Default::default() };
+            assert_eq!(iism.ws_lead_periods(), 1);
+        }
+        assert_eq!(IISM::default().ws_lead_periods(), 0);
+    }
+
+    fn only_error(regs: &HardRegisters, ic: ICVersion) -> ValidationError {
+        let errors = regs.validate(ic).unwrap_err();
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        errors[0]
+    }
+
+    #[test]
+    fn defaults_validate() {
+        for ic in [ICVersion::SX1255, ICVersion::SX1257] {
+            assert_eq!(HardRegisters::default_for(ic).validate(ic), Ok(()));
+        }
+    }
+
+    #[test]
+    fn validate_rejects_unused_lna_gain_codes() {
+        for code in [0, 7] {
+            let mut regs = HardRegisters::default_for(ICVersion::SX1257);
+            regs.rx_frontend.lna_gain = code;
+            assert_eq!(
+                only_error(&regs, ICVersion::SX1257),
+                ValidationError { register: 0x0C, field: "lna_gain", value: code as u32 }
+            );
+        }
+    }
+
+    #[test]
+    fn validate_rejects_too_many_dac_taps() {
+        let mut regs = HardRegisters::default_for(ICVersion::SX1255);
+        regs.tx_frontend_1255.dac_bw = 6;
+        assert_eq!(only_error(&regs, ICVersion::SX1255).field, "dac_bw");
+        // The register is never written to an SX1257.
+        assert_eq!(regs.validate(ICVersion::SX1257), Ok(()));
+    }
+
+    #[test]
+    fn validate_rejects_digital_bridge_out_of_range() {
+        let mut regs = HardRegisters::default_for(ICVersion::SX1255);
+        regs.digital_bridge.int_dec_n_parameter = 7;
+        assert_eq!(only_error(&regs, ICVersion::SX1255).field, "int_dec_n_parameter");
+
+        let mut regs = HardRegisters::default_for(ICVersion::SX1255);
+        regs.digital_bridge.int_dec_m_parameter = 2;
+        assert_eq!(only_error(&regs, ICVersion::SX1255).field, "int_dec_m_parameter");
+    }
+
+    #[test]
+    fn validate_rejects_iism_forced_off() {
+        let mut regs = HardRegisters::default_for(ICVersion::SX1255);
+        regs.digital_bridge.iism_status = true;
+        assert_eq!(
+            only_error(&regs, ICVersion::SX1255),
+            ValidationError { register: 0x13, field: "iism_status", value: 1 }
+        );
+    }
+
+    #[test]
+    fn register_addresses_match_the_serializer() {
+        use RegisterAddr as R;
+        let mut regs = HardRegisters::default_for(ICVersion::SX1255);
+        regs.mode.standby_enable = true;
+        regs.rx = Frequency::from_raw(0x010203);
+        regs.tx = Frequency::from_raw(0x040506);
+        regs.tx_frontend_1255.dac_bw = 5;
+        regs.clock_select.rf_loopback_enable = true;
+        regs.status.pll_lock_tx = true;
+        regs.digital_bridge.iism_status = true;
+        let mut bytes = [0u8; 0x1B];
+        regs.serialize(&mut bytes, ICVersion::SX1255);
+
+        assert_eq!(bytes[R::Mode.index()], 0x01);
+        assert_eq!(bytes[R::RxFreqMsb.index()], 0x01);
+        assert_eq!(bytes[R::RxFreqMid.index()], 0x02);
+        assert_eq!(bytes[R::RxFreqLsb.index()], 0x03);
+        assert_eq!(bytes[R::TxFreqMsb.index()], 0x04);
+        assert_eq!(bytes[R::TxFreqMid.index()], 0x05);
+        assert_eq!(bytes[R::TxFreqLsb.index()], 0x06);
+        assert_eq!(bytes[R::Version.index()], 0x1A);
+        assert_eq!(bytes[R::TxDacBw.index()], 0x05);
+        assert_eq!(bytes[R::RxGain.index()], 0x3F);
+        assert_eq!(bytes[R::ClockSelect.index()], 0x06);
+        assert_eq!(bytes[R::Status.index()], 0x01);
+        assert_eq!(bytes[R::DigitalBridge.index()], 0x02);
+
+        let mut regs = HardRegisters::default_for(ICVersion::SX1257);
+        regs.low_battery_threshold.threshold = ThresholdValue::V3_245;
+        regs.serialize(&mut bytes, ICVersion::SX1257);
+        assert_eq!(bytes[R::LowBatteryThreshold.index()], 0x07);
+
+        for register in RegisterAddr::ALL {
+            assert_eq!(RegisterAddr::from_addr(register as u8), Some(register));
+        }
+        assert_eq!(RegisterAddr::from_addr(0x14), None);
+        assert_eq!(RegisterAddr::from_addr(0x1B), None);
+    }
+
+    #[test]
+    fn frequency_divisor_bits_give_documented_steps() {
+        assert_eq!(ICVersion::SX1255.frequency_divisor_bits(), 20);
+        assert_eq!(ICVersion::SX1257.frequency_divisor_bits(), 19);
+        assert!((Frequency::step_hz(36e6, ICVersion::SX1255) - 34.3323).abs() < 1e-4);
+        assert!((Frequency::step_hz(36e6, ICVersion::SX1257) - 68.6646).abs() < 1e-4);
+    }
+
+    #[test]
+    fn transmit_power_limit() {
+        // 10 dBm measured at full gain, 0 dB DAC and -7.5 dB mixer.
+        let cal = TxPowerCalibration { measured_dbm: 10.0, gain_db: -7.5 };
+        let full = TxFrontend { dac_gain: 3, mixer_gain: 15, ..Default::default() };
+        assert_eq!(cal.output_dbm(&full), 10.0);
+        assert_eq!(full.assert_within_power_limit(6.0, &cal), Err(Error::PowerLimitExceeded));
+
+        // 6 dB less mixer gain.
+        let reduced = TxFrontend { dac_gain: 3, mixer_gain: 12, ..Default::default() };
+        assert_eq!(cal.output_dbm(&reduced), 4.0);
+        assert_eq!(reduced.assert_within_power_limit(6.0, &cal), Ok(()));
+    }
+
+    #[test]
+    fn frequency_change_is_a_three_entry_delta() {
+        let previous = sample_registers();
+        let mut new = sample_registers();
+        new.rx = Frequency::from_raw(new.rx.raw() + 0x010101);
+        let packet = new.delta_packet(&previous, ICVersion::SX1255);
+        assert_eq!(packet.len(), 3 * 2);
+        assert_eq!(packet.iter().step_by(2).copied().collect::<Vec<u8>>(), [0x01, 0x02, 0x03]);
+
+        let mut applied = sample_registers();
+        applied.apply_delta_packet(&packet, ICVersion::SX1255).unwrap();
+        assert_eq!(applied, new);
+        assert!(new.delta_packet(&applied, ICVersion::SX1255).is_empty());
+
+        assert!(applied.apply_delta_packet(&[0x01], ICVersion::SX1255).is_err());
+        assert!(applied.apply_delta_packet(&[0x11, 0x00], ICVersion::SX1255).is_err());
+        assert_eq!(
+            applied.apply_delta_packet(&[0x1A, 0x03], ICVersion::SX1255),
+            Err(Error::UnsupportedOnIcVersion("LowBatteryThreshold"))
+        );
+        // ADC bandwidth code 3 isn't documented.
+        assert_eq!(
+            applied.apply_delta_packet(&[0x0D, 0x74], ICVersion::SX1255),
+            Err(Error::InvalidRegisterValue("rx_frontend"))
+        );
+        assert_eq!(applied, new);
+    }
+
+    #[test]
+    fn frequency_msb_change_is_a_three_entry_delta() {
+        let mut previous = sample_registers();
+        previous.rx = Frequency::from_raw(0xC0E38E);
+        let mut new = previous.clone();
+        new.rx = Frequency::from_raw(0xC1E38E);
+        assert_eq!(
+            new.delta_packet(&previous, ICVersion::SX1255),
+            [0x01, 0xC1, 0x02, 0xE3, 0x03, 0x8E]
+        );
+
+        // Applied for the SX1257, the SX1255-only settings are kept.
+        let packet = new.delta_packet(&previous, ICVersion::SX1257);
+        let mut applied = previous.clone();
+        applied.apply_delta_packet(&packet, ICVersion::SX1257).unwrap();
+        assert_eq!(applied, new);
+    }
+
+    #[test]
+    fn battery_threshold_snaps_to_nearest_voltage() {
+        assert_eq!(ThresholdValue::nearest(2.7), ThresholdValue::V2_724);
+        assert_eq!(ThresholdValue::nearest(1.8), ThresholdValue::V2_516);
+        assert_eq!(ThresholdValue::nearest(5.0), ThresholdValue::V3_245);
+        for threshold in ThresholdValue::ALL {
+            assert_eq!(ThresholdValue::nearest(threshold.volts()), threshold);
+        }
+    }
+
+    #[test]
+    fn adc_bandwidth_picks_narrowest_covering_range() {
+        assert_eq!(RxADCBw::for_bandwidth(300), RxADCBw::BW200To400KHz);
+        assert_eq!(RxADCBw::for_bandwidth(50), RxADCBw::BW100To400KHz);
+        assert_eq!(RxADCBw::for_bandwidth(401), RxADCBw::BWOver400KHz);
+        assert_eq!(RxADCBw::for_bandwidth(u16::MAX), RxADCBw::BWOver400KHz);
+        assert_eq!(RxADCBw::for_bandwidth(400), RxADCBw::BW200To400KHz);
+        assert_eq!(RxADCBw::BW200To400KHz.min_khz(), 200);
+    }
+
+    #[test]
+    fn mixer_tank_resistance_snaps_to_nearest_tap() {
+        assert_eq!(TxMixerTankResistance::nearest(1200), TxMixerTankResistance::Ω1110);
+        assert_eq!(TxMixerTankResistance::nearest(0), TxMixerTankResistance::Ω950);
+        assert_eq!(TxMixerTankResistance::nearest(u32::MAX), TxMixerTankResistance::Ω64000);
+        for resistance in TxMixerTankResistance::ALL {
+            assert_eq!(TxMixerTankResistance::nearest(resistance.ohms()), resistance);
+        }
+    }
+
+    #[test]
+    fn transmit_filter_bandwidth_rounds_wider() {
+        let mut tx = TxFrontend1255::default();
+        assert!((tx.filter_bw_mhz() - 17.15 / 41.0).abs() < 1e-6);
+        assert_eq!(tx.tap_count(), 24);
+
+        tx.set_filter_bw_mhz(1.0);
+        assert_eq!(tx.filter_bw, 24);
+        assert!(tx.filter_bw_mhz() >= 1.0);
+        tx.set_filter_bw_mhz(17.15 / 20.0);
+        assert_eq!(tx.filter_bw, 21);
+
+        tx.set_filter_bw_mhz(0.0);
+        assert_eq!(tx.filter_bw, 0);
+        tx.set_filter_bw_mhz(100.0);
+        assert_eq!(tx.filter_bw, 31);
+        assert!((tx.filter_bw_mhz() - 1.715).abs() < 1e-6);
+    }
+
+    #[test]
+    fn transmit_tap_count_clamps_to_64() {
+        let mut tx = TxFrontend1255::default();
+        tx.set_tap_count(0);
+        assert_eq!(tx.dac_bw, 0);
+        tx.set_tap_count(40);
+        assert_eq!((tx.dac_bw, tx.tap_count()), (2, 40));
+        tx.set_tap_count(255);
+        assert_eq!((tx.dac_bw, tx.tap_count()), (5, 64));
+        tx.dac_bw = 7;
+        assert_eq!(tx.tap_count(), 64);
+    }
+
+    #[test]
+    fn diff_of_lna_gain_is_one_register() {
+        let old = HardRegisters::default_for(ICVersion::SX1255);
+        let mut new = HardRegisters::default_for(ICVersion::SX1255);
+        new.rx_frontend.lna_gain = 3;
+        let mut bytes = [0u8; 0x1B];
+        new.serialize(&mut bytes, ICVersion::SX1255);
+        assert_eq!(
+            new.diff(&old, ICVersion::SX1255),
+            [(RegisterAddr::RxGain as u8, bytes[RegisterAddr::RxGain.index()])]
+        );
+        assert_eq!(old.diff(&old, ICVersion::SX1255), []);
+    }
+
+    #[test]
+    fn diff_of_frequency_writes_all_three_bytes() {
+        let old = HardRegisters::default_for(ICVersion::SX1255);
+        let mut new = HardRegisters::default_for(ICVersion::SX1255);
+        new.tx = Frequency::from_raw(old.tx.raw() + 0x01_0000);
+        assert_eq!(
+            new.diff(&old, ICVersion::SX1255),
+            [(0x04, 0xC1), (0x05, 0xE3), (0x06, 0x8E)]
+        );
+    }
+
+    #[test]
+    fn version_identifies_the_ic() {
+        let sx1255 = Version { fill_revision_number: 1, metal_mask_revision_number: 0xA };
+        assert_eq!(sx1255.detect_ic(), Some(ICVersion::SX1255));
+        assert_eq!(Version::from_raw(DEFAULT_REGISTERS_SX1255[0x07]), sx1255);
+        assert_eq!(Version::from_raw(0x21).detect_ic(), Some(ICVersion::SX1257));
+        assert_eq!(Version::from_raw(0x00).detect_ic(), None);
+        assert_eq!(Version::from_raw(0xFF).detect_ic(), None);
+    }
+
+    #[test]
+    fn b2_interleave_round_trips() {
+        let i = [1, -2, 3, -4];
+        let q = [10, -20, 30, -40];
+        let line = interleave_b2(&i, &q);
+        assert_eq!(line, [1, 10, -2, -20, 3, 30, -4, -40]);
+        assert_eq!(deinterleave_b2(&line), (i.to_vec(), q.to_vec()));
+        assert_eq!(deinterleave_b2(&line[..3]), (vec![1], vec![10]));
+
+        let iism = IISM { mode: IISMMode::B2, ..Default::default() };
+        assert_eq!(iism.samples_per_ws(), 2);
+    }
+
+    #[test]
+    fn truncation_aligns_samples_on_the_bus() {
+        assert_eq!(IISMTruncation::MSB.to_bus(-3, 12), -3);
+        assert_eq!(IISMTruncation::LSB.to_bus(-3, 12), -3 << 20);
+        for truncation in [IISMTruncation::MSB, IISMTruncation::LSB] {
+            for sample in [-2048, -1, 0, 1, 2047] {
+                assert_eq!(truncation.from_bus(truncation.to_bus(sample, 12), 12), sample);
+            }
+        }
+    }
+
+    #[test]
+    fn duplex_rejects_a_disabled_direction() {
+        use crate::control::Mode as ControlMode;
+
+        let iism = IISM { mode: IISMMode::B2, ..Default::default() };
+        assert_eq!(iism.check_duplex(&ControlMode::FullDuplex), Ok(()));
+
+        let iism = IISM { rx_during_tx_disable: true, ..Default::default() };
+        assert_eq!(
+            iism.check_duplex(&ControlMode::FullDuplex),
+            Err(Error::InvalidRegisterValue("rx_during_tx_disable"))
+        );
+        assert_eq!(iism.check_duplex(&ControlMode::Receive), Ok(()));
+
+        let iism = IISM { tx_during_rx_disable: true, ..Default::default() };
+        assert_eq!(
+            iism.check_duplex(&ControlMode::FullDuplex),
+            Err(Error::InvalidRegisterValue("tx_during_rx_disable"))
+        );
+    }
+
+    #[test]
+    fn io_map_for_indications() {
+        assert_eq!(IOMap::for_indications(true, false).iomap0, IOMap0::PLLLockRx);
+        assert_eq!(IOMap::for_indications(false, true).iomap0, IOMap0::Eol);
+        assert_eq!(IOMap::for_indications(true, true).iomap0, IOMap0::PLLLockRx);
+        assert_eq!(IOMap::for_indications(false, false), IOMap::default());
+    }
+
+    #[test]
+    fn report_shows_frequency_and_gains() {
+        let mut regs = HardRegisters::default_for(ICVersion::SX1255);
+        regs.mode.standby_enable = true;
+        regs.mode.rx_enable = true;
+        regs.rx = Frequency::from_hz(434e6, 36e6, ICVersion::SX1255);
+        regs.rx_frontend.lna_gain = 3;
+        regs.status.pll_lock_rx = true;
+
+        let report = regs.report(36e6, ICVersion::SX1255);
+        assert!(report.contains("Mode: receive"));
+        assert!(report.contains("RX frequency: 433.999"));
+        assert!(report.contains("RX LNA gain: -12 dB"));
+        assert!(report.contains("RX PLL locked"));
+        assert!(report.contains("I²S mode: A"));
+        assert!(!report.contains("Low battery"));
+
+        let report = regs.report(36e6, ICVersion::SX1257);
+        assert!(report.contains("Low battery threshold: 2.516 V"));
+        assert!(!report.contains("I²S") && !report.contains("tank"));
+    }
+
+    #[test]
+    fn lna_gain_enum_uses_only_legal_codes() {
+        let mut byte = [0u8];
+        RxLnaGain::G0dB.binary_serialize(&mut byte, Endianness::Big);
+        assert_eq!(byte, [1]);
+        for gain in RxLnaGain::ALL {
+            let code = gain as u8;
+            assert!((1..=6).contains(&code));
+            assert_eq!(RxFrontend::lna_gain_code(RxFrontend::lna_gain_from_code(code)), code);
+        }
+        assert_eq!(RxLnaGain::nearest(3.0), RxLnaGain::G0dB);
+        assert_eq!(RxLnaGain::nearest(-3.0), RxLnaGain::G0dB);
+        assert_eq!(RxLnaGain::nearest(-19.0), RxLnaGain::Gm24dB);
+        assert_eq!(RxLnaGain::nearest(-60.0), RxLnaGain::Gm48dB);
+        assert_eq!(RxLnaGain::from_code(0), RxLnaGain::G0dB);
+        assert_eq!(RxLnaGain::from_code(7), RxLnaGain::Gm48dB);
+
+        let mut rx = RxFrontend::default();
+        rx.set_lna(RxLnaGain::Gm24dB);
+        assert_eq!((rx.lna_gain, rx.lna()), (4, RxLnaGain::Gm24dB));
+        assert!(RxLnaGain::G0dB.is_best_for_sensitivity());
+        assert!(!rx.lna().is_best_for_sensitivity());
+    }
+
+    #[test]
+    fn clone_serializes_identically() {
+        let source = sample_registers();
+        let clone = source.clone();
+        let (mut a, mut b) = ([0u8; 0x1B], [0u8; 0x1B]);
+        source.serialize(&mut a, ICVersion::SX1255);
+        clone.serialize(&mut b, ICVersion::SX1255);
+        assert_eq!(a, b);
+        assert_eq!(clone, source);
+    }
+
+    #[test]
+    fn preset_table_keeps_registers_by_name() {
+        let mut presets = PresetTable::new();
+        presets.insert("rx", sample_registers());
+        presets.insert("idle", HardRegisters::default());
+        assert_eq!(presets.names().collect::<Vec<_>>(), ["idle", "rx"]);
+        assert_eq!(presets.get("rx"), Some(&sample_registers()));
+        assert_eq!(presets.get("tx"), None);
+    }
+
+    #[test]
+    fn frequency_constructors_check_bounds() {
+        assert_eq!(Frequency::new(Frequency::MAX), Some(Frequency::from_raw(Frequency::MAX)));
+        assert_eq!(Frequency::new(Frequency::MAX + 1), None);
+
+        assert_eq!(
+            Frequency::checked_from_hz(434e6, 36e6, ICVersion::SX1255),
+            Ok(Frequency::from_hz(434e6, 36e6, ICVersion::SX1255))
+        );
+        assert_eq!(
+            Frequency::checked_from_hz(512e6, 32e6, ICVersion::SX1255),
+            Err(Error::FrequencyOutOfRange)
+        );
+        assert!(Frequency::checked_from_hz(511e6, 32e6, ICVersion::SX1255).is_ok());
+        assert!(Frequency::checked_from_hz(399e6, 36e6, ICVersion::SX1255).is_err());
+        assert!(Frequency::checked_from_hz(1001e6, 36e6, ICVersion::SX1257).is_err());
+    }
+
+    #[test]
+    fn dac_gain_db_never_sets_test_vref() {
+        let mut tx = TxFrontend::default();
+        tx.set_dac_gain_db(0.0);
+        assert_eq!(tx.dac_gain, 3);
+        tx.set_dac_gain_db(-9.0);
+        assert_eq!(tx.dac_gain, 0);
+        tx.set_dac_gain_db(20.0);
+        assert_eq!(tx.dac_gain, 3);
+        assert_eq!(tx.dac_gain_db(), 0.0);
+
+        tx.enable_dac_test_vref(true);
+        assert_eq!(tx.dac_gain, 7);
+        assert_eq!(tx.dac_gain_db(), 0.0);
+        tx.set_dac_gain_db(-6.0);
+        assert_eq!(tx.dac_gain, 1);
+        tx.enable_dac_test_vref(true);
+        tx.enable_dac_test_vref(false);
+        assert_eq!(tx.dac_gain, 1);
+
+        tx.set_mixer_gain_db(-7.5);
+        assert_eq!((tx.mixer_gain, tx.mixer_gain_db()), (15, -7.5));
+        tx.set_mixer_gain_db(-100.0);
+        assert_eq!((tx.mixer_gain, tx.mixer_gain_db()), (0, -37.5));
+    }
+
+    #[test]
+    fn frequency_serializes_to_exactly_three_bytes() {
+        // The fourth byte is a sentinel, it must not be touched.
+        let mut bytes = [0xAAu8; 4];
+        Frequency::from_raw(0xFF_12_34_56).binary_serialize(&mut bytes[0..3], Endianness::Big);
+        assert_eq!(bytes, [0x12, 0x34, 0x56, 0xAA]);
+
+        let mut bytes = [0xAAu8; 4];
+        Frequency::from_raw(0xFF_00_00_00).binary_serialize(&mut bytes[0..3], Endianness::Big);
+        assert_eq!(bytes, [0x00, 0x00, 0x00, 0xAA]);
+    }
+
+    /// Property tests: any legal field values survive serialize and
+    /// deserialize, and the bytes that don't belong to the IC stay zero.
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+
+        /// The enum variant with the register code `code`.
+        fn variant<T: BinarySerde>(code: u8) -> T {
+            T::binary_deserialize(&[code], Endianness::Big).unwrap()
+        }
+
+        /// One of the register codes in `codes`, as an enum variant.
+        fn one_of<T: BinarySerde + core::fmt::Debug>(codes: &[u8]) -> impl Strategy<Value = T> {
+            prop::sample::select(codes.to_vec()).prop_map(variant::<T>)
+        }
+
+        prop_compose! {
+            fn mode()(bits in 0..16u8) -> Mode {
+                Mode {
+                    driver_enable: bits & 8 != 0,
+                    tx_enable: bits & 4 != 0,
+                    rx_enable: bits & 2 != 0,
+                    standby_enable: bits & 1 != 0,
+                    ..Default::default()
+                }
+            }
+        }
+
+        prop_compose! {
+            fn frequency()(raw in 0..=Frequency::MAX) -> Frequency {
+                Frequency::from_raw(raw)
+            }
+        }
+
+        prop_compose! {
+            fn version()(fill in 0..16u8, metal in 0..16u8) -> Version {
+                Version { fill_revision_number: fill, metal_mask_revision_number: metal }
+            }
+        }
+
+        prop_compose! {
+            fn tx_frontend()(dac_gain in 0..8u8, mixer_gain in 0..16u8) -> TxFrontend {
+                TxFrontend { dac_gain, mixer_gain, ..Default::default() }
+            }
+        }
+
+        prop_compose! {
+            fn tx_frontend_1255()(
+                mixer_tank_cap in 0..8u8,
+                mixer_tank_resistance in one_of::<TxMixerTankResistance>(&[0, 1, 2, 3, 4, 5, 6, 7]),
+                pll_bw in 0..4u8,
+                filter_bw in 0..32u8,
+                dac_bw in 0..=5u8,
+            ) -> TxFrontend1255 {
+                TxFrontend1255 {
+                    mixer_tank_cap,
+                    mixer_tank_resistance,
+                    pll_bw,
+                    filter_bw,
+                    dac_bw,
+                    ..Default::default()
+                }
+            }
+        }
+
+        prop_compose! {
+            fn rx_frontend()(
+                lna_gain in 1..=6u8,
+                baseband_gain in 0..16u8,
+                zin in one_of::<RxZIn>(&[0, 1]),
+                adc_bw in one_of::<RxADCBw>(&[1, 2, 5, 7]),
+                adc_trim in one_of::<RxADCTrim>(&[5, 6]),
+                pga_bw in one_of::<RxPGABw>(&[0, 1, 2, 3]),
+                pll_bw in 0..4u8,
+                adc_temp in any::<bool>(),
+            ) -> RxFrontend {
+                RxFrontend {
+                    lna_gain,
+                    baseband_gain,
+                    zin,
+                    adc_bw,
+                    adc_trim,
+                    pga_bw,
+                    pll_bw,
+                    adc_temp,
+                    ..Default::default()
+                }
+            }
+        }
+
+        prop_compose! {
+            fn io_map()(iomap0 in one_of::<IOMap0>(&[0, 1, 2, 3])) -> IOMap {
+                IOMap { iomap0, ..Default::default() }
+            }
+        }
+
+        prop_compose! {
+            fn clock_select()(
+                bits in 0..8u8,
+                clock_select_tx_dac in one_of::<ClockSelectTxDAC>(&[0, 1]),
+            ) -> ClockSelect {
+                ClockSelect {
+                    dig_loopback_enable: bits & 4 != 0,
+                    rf_loopback_enable: bits & 2 != 0,
+                    clock_output_enable: bits & 1 != 0,
+                    clock_select_tx_dac,
+                    ..Default::default()
+                }
+            }
+        }
+
+        prop_compose! {
+            fn status()(bits in 0..16u8) -> Status {
+                Status {
+                    eol: bits & 8 != 0,
+                    xosc_ready: bits & 4 != 0,
+                    pll_lock_rx: bits & 2 != 0,
+                    pll_lock_tx: bits & 1 != 0,
+                    ..Default::default()
+                }
+            }
+        }
+
+        prop_compose! {
+            fn iism()(
+                bits in 0..4u8,
+                mode in one_of::<IISMMode>(&[0, 1, 2]),
+                clock_div in one_of::<IISMClockDiv>(&[0, 1, 2, 3, 4, 5, 6, 7, 8]),
+            ) -> IISM {
+                IISM {
+                    rx_during_tx_disable: bits & 2 != 0,
+                    tx_during_rx_disable: bits & 1 != 0,
+                    mode,
+                    clock_div,
+                }
+            }
+        }
+
+        prop_compose! {
+            fn digital_bridge()(
+                int_dec_mantissa in one_of::<IntDecMantissa>(&[0, 1]),
+                int_dec_m_parameter in 0..=1u8,
+                int_dec_n_parameter in 0..=DigitalBridge::MAX_N,
+                iism_truncation in one_of::<IISMTruncation>(&[0, 1]),
+                iism_status in any::<bool>(),
+            ) -> DigitalBridge {
+                DigitalBridge {
+                    int_dec_mantissa,
+                    int_dec_m_parameter,
+                    int_dec_n_parameter,
+                    iism_truncation,
+                    iism_status,
+                    ..Default::default()
+                }
+            }
+        }
+
+        prop_compose! {
+            fn low_battery_threshold()(
+                threshold in one_of::<ThresholdValue>(&[0, 1, 2, 3, 4, 5, 6, 7]),
+            ) -> LowBatteryThreshold {
+                LowBatteryThreshold { threshold, ..Default::default() }
+            }
+        }
+
+        prop_compose! {
+            fn common()(
+                mode in mode(),
+                rx in frequency(),
+                tx in frequency(),
+                version in version(),
+                tx_frontend in tx_frontend(),
+                rx_frontend in rx_frontend(),
+                io_map in io_map(),
+                clock_select in clock_select(),
+                status in status(),
+            ) -> HardRegisters {
+                HardRegisters {
+                    mode,
+                    rx,
+                    tx,
+                    version,
+                    tx_frontend,
+                    rx_frontend,
+                    io_map,
+                    clock_select,
+                    status,
+                    ..Default::default()
+                }
+            }
+        }
+
+        prop_compose! {
+            fn sx1255_registers()(
+                registers in common(),
+                tx_frontend_1255 in tx_frontend_1255(),
+                iism in iism(),
+                digital_bridge in digital_bridge(),
+            ) -> HardRegisters {
+                HardRegisters { tx_frontend_1255, iism, digital_bridge, ..registers }
+            }
+        }
+
+        prop_compose! {
+            fn sx1257_registers()(
+                registers in common(),
+                low_battery_threshold in low_battery_threshold(),
+            ) -> HardRegisters {
+                HardRegisters { low_battery_threshold, ..registers }
+            }
+        }
+
+        proptest! {
+            #[test]
+            fn sx1255_round_trips(registers in sx1255_registers()) {
+                let mut bytes = [0xFFu8; 0x1B];
+                registers.serialize(&mut bytes, ICVersion::SX1255);
+                prop_assert!(bytes[0x14..=0x1A].iter().all(|byte| *byte == 0));
+                let decoded = HardRegisters::deserialize(&bytes, ICVersion::SX1255).unwrap();
+                prop_assert_eq!(decoded, registers);
+            }
+
+            #[test]
+            fn sx1257_round_trips(registers in sx1257_registers()) {
+                let mut bytes = [0xFFu8; 0x1B];
+                registers.serialize(&mut bytes, ICVersion::SX1257);
+                prop_assert!(bytes[0x09..=0x0B].iter().all(|byte| *byte == 0));
+                prop_assert!(bytes[0x12..=0x19].iter().all(|byte| *byte == 0));
+                let decoded = HardRegisters::deserialize(&bytes, ICVersion::SX1257).unwrap();
+                prop_assert_eq!(decoded, registers);
+            }
+
+            #[test]
+            fn sx1255_settings_are_dropped_on_sx1257(registers in sx1255_registers()) {
+                let mut bytes = [0xFFu8; 0x1B];
+                registers.serialize(&mut bytes, ICVersion::SX1257);
+                prop_assert!(bytes[0x09..=0x0B].iter().all(|byte| *byte == 0));
+                prop_assert!(bytes[0x12..=0x19].iter().all(|byte| *byte == 0));
+                prop_assert_eq!(bytes[0x1A], 0);
+            }
+        }
+    }
+}
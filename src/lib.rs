@@ -1,4 +1,13 @@
 //! Rust software for Semtech SX1255
+//!
+//! This crate is `no_std` when built without the default `std` feature, since
+//! the IC is usually controlled from a bare-metal microcontroller. The
+//! `alloc` feature enables the parts that need a heap, and is implied by
+//! `std`.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
 
 #[doc = include_str!("../markdown/hard_registers.md")]
 pub mod hard_registers;
@@ -6,6 +15,16 @@ pub mod hard_registers;
 #[doc = include_str!("../markdown/control.md")]
 pub mod control;
 
+#[doc = include_str!("../markdown/dsp.md")]
+pub mod dsp;
+
+#[cfg(feature = "transport")]
+#[doc = include_str!("../markdown/transport.md")]
+pub mod transport;
+
+pub mod error;
+pub use error::Error;
+
 #[cfg(test)]
 mod tests {
     use super::*;
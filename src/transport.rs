@@ -0,0 +1,780 @@
+// Copyright (C) 2025 Bruce Perens
+// All Rights Reserved
+// This software is not presently under an Open Source license, I'll consider
+// what to do about that if someone pays me to do so, or when I'm done.
+
+use crate::control::{self, Mode};
+use crate::dsp::{self, IqCalibration};
+use crate::hard_registers::{
+    self, Frequency, HardRegisters, ICVersion, RegisterAddr, Version, DEFAULT_REGISTERS_SX1255,
+    DEFAULT_REGISTERS_SX1257,
+};
+use crate::Error;
+use core::time::Duration;
+use embedded_hal::delay::DelayNs;
+use embedded_hal::spi::SpiDevice;
+
+#[cfg(feature = "async")]
+mod asynch;
+#[cfg(feature = "async")]
+pub use asynch::Sx125xAsync;
+
+/// The number of register addresses, 0x00 through 0x1A.
+const REGISTER_COUNT: usize = 0x1B;
+
+/// Set in the address byte for a write, clear for a read.
+const WRITE_BIT: u8 = 0x80;
+
+/// Bits of the mode register at 0x00, as in
+/// [hard_registers::Mode](struct@crate::hard_registers::Mode).
+const MODE_STANDBY: u8 = 0x01;
+const MODE_RX: u8 = 0x02;
+const MODE_TX: u8 = 0x04;
+const MODE_DRIVER: u8 = 0x08;
+
+/// Bits of the status register at 0x11, as in
+/// [hard_registers::Status](struct@crate::hard_registers::Status).
+const STATUS_XOSC_READY: u8 = 0x04;
+const STATUS_PLL_LOCK_RX: u8 = 0x02;
+const STATUS_PLL_LOCK_TX: u8 = 0x01;
+
+/// The rf_loopback_enable bit of the clock select register at 0x10, as in
+/// [hard_registers::ClockSelect](struct@crate::hard_registers::ClockSelect).
+const CLOCK_SELECT_RF_LOOPBACK: u8 = 0x04;
+
+/// The clock_output_enable bit of the clock select register at 0x10.
+const CLOCK_SELECT_CLOCK_OUTPUT: u8 = 0x02;
+
+/// How often the status register is polled while waiting, in microseconds.
+const POLL_INTERVAL_US: u32 = 10;
+
+/// The longest synthesizer lock time the data sheet gives as typical, from
+/// wake-up.
+const PLL_LOCK_TYPICAL: Duration = Duration::from_micros(150);
+
+/// The mode register bits for `mode`. Transmit includes the power
+/// amplifier, as in [Control::to_hard_registers](crate::control::Control::to_hard_registers).
+fn mode_bits(mode: Mode) -> u8 {
+    match mode {
+        Mode::Sleep => 0,
+        Mode::Standby => MODE_STANDBY,
+        Mode::Receive => MODE_STANDBY | MODE_RX,
+        Mode::Transmit => MODE_STANDBY | MODE_TX | MODE_DRIVER,
+        Mode::FullDuplex => MODE_STANDBY | MODE_RX | MODE_TX | MODE_DRIVER,
+    }
+}
+
+/// The SPI frame that writes `value` to the register at `addr`.
+pub(crate) fn write_frame(addr: u8, value: u8) -> [u8; 2] {
+    [addr | WRITE_BIT, value]
+}
+
+/// The SPI frame that reads the register at `addr`. The value is clocked
+/// out into the second byte.
+pub(crate) fn read_frame(addr: u8) -> [u8; 2] {
+    [addr & !WRITE_BIT, 0]
+}
+
+/// Whether the register at `addr` should be written on `ic_version`. See
+/// [RegisterAddr::writable]; unused addresses are never written.
+pub(crate) fn writable(addr: u8, ic_version: ICVersion) -> bool {
+    RegisterAddr::from_addr(addr).is_some_and(|register| register.writable(ic_version))
+}
+
+/// The register writes, in order, that put serialized registers into the
+/// IC: ascending, skipping read-only registers and those not documented for
+/// `ic_version`.
+pub(crate) fn writes(
+    bytes: &[u8; REGISTER_COUNT],
+    ic_version: ICVersion,
+) -> impl Iterator<Item = (u8, u8)> + '_ {
+    (0..REGISTER_COUNT as u8)
+        .zip(bytes.iter().copied())
+        .filter(move |(addr, _)| writable(*addr, ic_version))
+}
+
+/// The reset-default registers of `ic_version`.
+pub(crate) fn defaults(ic_version: ICVersion) -> &'static [u8; REGISTER_COUNT] {
+    match ic_version {
+        ICVersion::SX1255 => &DEFAULT_REGISTERS_SX1255,
+        ICVersion::SX1257 => &DEFAULT_REGISTERS_SX1257,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Which synthesizer to wait for in `wait_pll_lock`.
+pub enum PllSelect {
+    Rx,
+    Tx,
+    /// Both the receive and transmit synthesizers, for full duplex.
+    Both,
+}
+
+impl PllSelect {
+    /// The status register bits that are set when the selection is locked.
+    fn status_bits(&self) -> u8 {
+        match self {
+            PllSelect::Rx => STATUS_PLL_LOCK_RX,
+            PllSelect::Tx => STATUS_PLL_LOCK_TX,
+            PllSelect::Both => STATUS_PLL_LOCK_RX | STATUS_PLL_LOCK_TX,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// How long `wait_pll_lock` waited for lock. A synthesizer that never locks
+/// is an [Error::Timeout] instead.
+pub enum LockTiming {
+    /// Locked within the typical 150 µs of the data sheet.
+    Typical,
+    /// Locked, but only after the given time, slower than typical.
+    Slow(Duration),
+}
+
+impl LockTiming {
+    /// Classify a lock seen after `elapsed`.
+    fn after(elapsed: Duration) -> LockTiming {
+        if elapsed <= PLL_LOCK_TYPICAL {
+            LockTiming::Typical
+        } else {
+            LockTiming::Slow(elapsed)
+        }
+    }
+}
+
+/// An SX1255 or SX1257 on an SPI bus.
+pub struct Sx125x<SPI> {
+    spi: SPI,
+    ic_version: ICVersion,
+}
+
+impl<SPI: SpiDevice> Sx125x<SPI> {
+    pub fn new(spi: SPI, ic_version: ICVersion) -> Self {
+        Sx125x { spi, ic_version }
+    }
+
+    /// The IC version this transport was created for.
+    pub fn ic_version(&self) -> ICVersion {
+        self.ic_version
+    }
+
+    /// Give back the SPI device.
+    pub fn release(self) -> SPI {
+        self.spi
+    }
+
+    /// Read the version register to find out which IC this is, and use that
+    /// version from now on. See [Version::detect_ic] for how certain that
+    /// is. Fails with [Error::InvalidRegisterValue] if no IC answers.
+    pub fn probe(&mut self) -> Result<ICVersion, Error<SPI::Error>> {
+        let raw = self.read_register(RegisterAddr::Version as u8)?;
+        let ic_version = Version::from_raw(raw)
+            .detect_ic()
+            .ok_or(Error::InvalidRegisterValue("version"))?;
+        self.ic_version = ic_version;
+        Ok(ic_version)
+    }
+
+    /// Read one register.
+    pub fn read_register(&mut self, addr: u8) -> Result<u8, Error<SPI::Error>> {
+        let mut frame = read_frame(addr);
+        self.spi.transfer_in_place(&mut frame).map_err(Error::Spi)?;
+        Ok(frame[1])
+    }
+
+    /// Write one register.
+    pub fn write_register(&mut self, addr: u8, value: u8) -> Result<(), Error<SPI::Error>> {
+        self.spi.write(&write_frame(addr, value)).map_err(Error::Spi)
+    }
+
+    /// Read and decode the status register: the PLL locks, oscillator ready
+    /// and battery low.
+    pub fn read_status(&mut self) -> Result<control::Status, Error<SPI::Error>> {
+        let raw = self.read_register(RegisterAddr::Status as u8)?;
+        let status = hard_registers::Status::from_raw(raw);
+        Ok(control::Status::from_hard(&status, self.ic_version))
+    }
+
+    /// Read the entire register bank. A register holding a code that
+    /// [HardRegisters::deserialize] doesn't describe fails with
+    /// [Error::InvalidRegisterValue].
+    pub fn read_all(&mut self) -> Result<HardRegisters, Error<SPI::Error>> {
+        let mut bytes = [0u8; REGISTER_COUNT];
+        for (addr, byte) in bytes.iter_mut().enumerate() {
+            *byte = self.read_register(addr as u8)?;
+        }
+        HardRegisters::deserialize(&bytes, self.ic_version).map_err(Error::into_bus_error)
+    }
+
+    /// Read the entire register bank and decode it into a [Control](control::Control),
+    /// the read-side counterpart of [to_hard_registers](control::Control::to_hard_registers).
+    /// `crystal_mhz` is the crystal frequency in MHz.
+    pub fn read_control(
+        &mut self,
+        crystal_mhz: f64,
+    ) -> Result<control::Control, Error<SPI::Error>> {
+        let registers = self.read_all()?;
+        Ok(control::Control::from_hard_registers(&registers, crystal_mhz, self.ic_version))
+    }
+
+    /// Write the entire register bank, except for read-only registers and
+    /// those not documented for the IC.
+    pub fn write_all(&mut self, registers: &HardRegisters) -> Result<(), Error<SPI::Error>> {
+        let mut bytes = [0u8; REGISTER_COUNT];
+        registers.serialize(&mut bytes, self.ic_version);
+        self.write_bytes(&bytes)
+    }
+
+    /// Write only the registers that differ between `old`, what the IC
+    /// holds now, and `new`. A changed frequency is written in full, so that
+    /// it latches. See [HardRegisters::diff].
+    pub fn write_diff(
+        &mut self,
+        old: &HardRegisters,
+        new: &HardRegisters,
+    ) -> Result<(), Error<SPI::Error>> {
+        for (addr, value) in new.diff_iter(old, self.ic_version) {
+            self.write_register(addr, value)?;
+        }
+        Ok(())
+    }
+
+    /// Return the IC to its reset state through the register interface, for
+    /// when the reset pin isn't wired. Registers are written in ascending
+    /// order, so the mode register goes first and puts the IC to sleep
+    /// before anything else changes under it.
+    pub fn reset(&mut self) -> Result<(), Error<SPI::Error>> {
+        self.write_bytes(defaults(self.ic_version))
+    }
+
+    /// Retune with the least SPI traffic: only the three bytes of each given
+    /// frequency are written, most significant first, so that the write of
+    /// the least significant byte latches the complete value.
+    pub fn hop_to(
+        &mut self,
+        rx: Option<&Frequency>,
+        tx: Option<&Frequency>,
+    ) -> Result<(), Error<SPI::Error>> {
+        if let Some(rx) = rx {
+            self.write_frequency(RegisterAddr::RxFreqMsb, rx)?;
+        }
+        if let Some(tx) = tx {
+            self.write_frequency(RegisterAddr::TxFreqMsb, tx)?;
+        }
+        Ok(())
+    }
+
+    /// Step the receiver through `channels`, calling `dwell` on each once it
+    /// has been written. `dwell` should wait out the hop time and take its
+    /// measurement.
+    pub fn scan<F: FnMut(&Frequency)>(
+        &mut self,
+        channels: &[Frequency],
+        mut dwell: F,
+    ) -> Result<(), Error<SPI::Error>> {
+        for channel in channels {
+            self.hop_to(Some(channel), None)?;
+            dwell(channel);
+        }
+        Ok(())
+    }
+
+    /// Write a frequency to the three registers starting at `msb`, least
+    /// significant byte last.
+    fn write_frequency(
+        &mut self,
+        msb: RegisterAddr,
+        frequency: &Frequency,
+    ) -> Result<(), Error<SPI::Error>> {
+        for (offset, byte) in frequency.raw().to_be_bytes()[1..].iter().enumerate() {
+            self.write_register(msb as u8 + offset as u8, *byte)?;
+        }
+        Ok(())
+    }
+
+    /// Measure the I/Q gain mismatch, phase imbalance and DC offset of the
+    /// transmit-to-receive path in RF loop-back. The IC should already be in
+    /// full duplex, with the receiver and transmitter tuned so that the
+    /// transmitted tone lands away from DC.
+    ///
+    /// `capture` is called with RF loop-back enabled. It should transmit a
+    /// known tone and fill `samples` with the I/Q samples received, through
+    /// whatever I²S path the host has. The clock select register is restored
+    /// to its prior value afterward, even if `capture` fails. Fails with
+    /// [Error::NoSignal] if I or Q received nothing.
+    pub fn calibrate_iq<F>(
+        &mut self,
+        samples: &mut [(f32, f32)],
+        capture: F,
+    ) -> Result<IqCalibration, Error<SPI::Error>>
+    where
+        F: FnOnce(&mut [(f32, f32)]) -> Result<(), Error<SPI::Error>>,
+    {
+        let clock_select = RegisterAddr::ClockSelect as u8;
+        let prior = self.read_register(clock_select)?;
+        let result = self
+            .write_register(clock_select, prior | CLOCK_SELECT_RF_LOOPBACK)
+            .and_then(|()| capture(samples));
+        let restored = self.write_register(clock_select, prior);
+        result?;
+        restored?;
+        dsp::estimate_iq_imbalance(samples).ok_or(Error::NoSignal)
+    }
+
+    /// Turn the CLK_OUT pin on or off. Only the clock_output_enable bit of
+    /// the clock select register changes; the loop-back and TX DAC clock
+    /// bits keep their values.
+    pub fn set_clock_output(&mut self, on: bool) -> Result<(), Error<SPI::Error>> {
+        let clock_select = RegisterAddr::ClockSelect as u8;
+        let prior = self.read_register(clock_select)?;
+        let value = if on {
+            prior | CLOCK_SELECT_CLOCK_OUTPUT
+        } else {
+            prior & !CLOCK_SELECT_CLOCK_OUTPUT
+        };
+        self.write_register(clock_select, value)
+    }
+
+    /// Change the operating mode one step at a time, so that each block is
+    /// powered only once what it depends on is ready. See the transition
+    /// table in the module documentation. Each wait polls the status
+    /// register until the ready bit is set, or fails with [Error::Timeout]
+    /// after `timeout`.
+    pub fn set_mode<D: DelayNs>(
+        &mut self,
+        mode: Mode,
+        delay: &mut D,
+        timeout: Duration,
+    ) -> Result<(), Error<SPI::Error>> {
+        let target = mode_bits(mode);
+        let mode_addr = RegisterAddr::Mode as u8;
+        let mut current = self.read_register(mode_addr)? & 0x0F;
+
+        // Power down: the amplifier before the transmitter, then the rest.
+        if current & MODE_DRIVER != 0 && target & MODE_DRIVER == 0 {
+            current &= !MODE_DRIVER;
+            self.write_register(mode_addr, current)?;
+        }
+        if current & !target != 0 {
+            current &= target;
+            self.write_register(mode_addr, current)?;
+        }
+
+        // Power up, waiting for each step to become ready.
+        let steps = [
+            (MODE_STANDBY, STATUS_XOSC_READY),
+            (MODE_RX, STATUS_PLL_LOCK_RX),
+            (MODE_TX, STATUS_PLL_LOCK_TX),
+            (MODE_DRIVER, 0),
+        ];
+        for (bit, ready) in steps {
+            if target & bit != 0 && current & bit == 0 {
+                current |= bit;
+                self.write_register(mode_addr, current)?;
+                if ready != 0 {
+                    self.wait_status(ready, delay, timeout)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Wait for the selected synthesizers to lock after a change of
+    /// frequency, polling the status register every 10 µs. The data sheet
+    /// gives 50 to 150 µs from wake-up, and 20 to 50 µs for a small hop; a
+    /// slower lock is reported as [LockTiming::Slow]. Fails with
+    /// [Error::Timeout] if there is no lock after `timeout`. A synthesizer
+    /// that repeatedly never locks suggests a frequency outside the band of
+    /// the IC, or a bad crystal.
+    pub fn wait_pll_lock<D: DelayNs>(
+        &mut self,
+        which: PllSelect,
+        delay: &mut D,
+        timeout: Duration,
+    ) -> Result<LockTiming, Error<SPI::Error>> {
+        let waited = self.wait_status(which.status_bits(), delay, timeout)?;
+        Ok(LockTiming::after(waited))
+    }
+
+    /// Poll the status register until all of the `bits` are set, returning
+    /// the time waited.
+    fn wait_status<D: DelayNs>(
+        &mut self,
+        bits: u8,
+        delay: &mut D,
+        timeout: Duration,
+    ) -> Result<Duration, Error<SPI::Error>> {
+        let mut waited = Duration::ZERO;
+        loop {
+            if self.read_register(RegisterAddr::Status as u8)? & bits == bits {
+                return Ok(waited);
+            }
+            if waited >= timeout {
+                return Err(Error::Timeout);
+            }
+            delay.delay_us(POLL_INTERVAL_US);
+            waited = waited.saturating_add(Duration::from_micros(POLL_INTERVAL_US.into()));
+        }
+    }
+
+    /// Write serialized registers in ascending order, except for read-only
+    /// registers and those not documented for the IC.
+    fn write_bytes(&mut self, bytes: &[u8; REGISTER_COUNT]) -> Result<(), Error<SPI::Error>> {
+        for (addr, byte) in writes(bytes, self.ic_version) {
+            self.write_register(addr, byte)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::hard_registers::*;
+    use core::convert::Infallible;
+    use embedded_hal::spi::{ErrorType, Operation};
+
+    /// A simulated IC: a register file that answers the SPI framing, and a
+    /// log of every register write in order.
+    pub(crate) struct MockChip {
+        pub registers: [u8; REGISTER_COUNT],
+        pub writes: Vec<(u8, u8)>,
+        /// Status bits to set once the status register has been read this
+        /// many times, to simulate a synthesizer locking.
+        pub status_after_reads: Option<(usize, u8)>,
+    }
+
+    impl MockChip {
+        pub fn new(registers: [u8; REGISTER_COUNT]) -> Self {
+            MockChip { registers, writes: Vec::new(), status_after_reads: None }
+        }
+    }
+
+    impl ErrorType for MockChip {
+        type Error = Infallible;
+    }
+
+    impl SpiDevice for MockChip {
+        fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Infallible> {
+            for operation in operations {
+                match operation {
+                    Operation::Write(frame) => {
+                        assert!(frame.len() == 2 && frame[0] & WRITE_BIT != 0);
+                        let addr = frame[0] & !WRITE_BIT;
+                        self.registers[addr as usize] = frame[1];
+                        self.writes.push((addr, frame[1]));
+                    }
+                    Operation::TransferInPlace(frame) => {
+                        assert!(frame.len() == 2 && frame[0] & WRITE_BIT == 0);
+                        frame[1] = self.registers[frame[0] as usize];
+                        if frame[0] == RegisterAddr::Status as u8 {
+                            if let Some((reads, bits)) = &mut self.status_after_reads {
+                                *reads = reads.saturating_sub(1);
+                                if *reads == 0 {
+                                    self.registers[frame[0] as usize] |= *bits;
+                                }
+                            }
+                        }
+                    }
+                    _ => panic!("unexpected SPI operation"),
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn register_framing() {
+        assert_eq!(write_frame(0x0C, 0x55), [0x8C, 0x55]);
+        assert_eq!(read_frame(0x8C), [0x0C, 0]);
+
+        let mut radio = Sx125x::new(MockChip::new([0; REGISTER_COUNT]), ICVersion::SX1255);
+        radio.write_register(0x0C, 0x55).unwrap();
+        assert_eq!(radio.read_register(0x0C), Ok(0x55));
+        assert_eq!(radio.release().writes, [(0x0C, 0x55)]);
+    }
+
+    #[test]
+    fn probe_detects_the_ic() {
+        let mut radio = Sx125x::new(MockChip::new(DEFAULT_REGISTERS_SX1255), ICVersion::SX1257);
+        assert_eq!(radio.probe(), Ok(ICVersion::SX1255));
+        assert_eq!(radio.ic_version(), ICVersion::SX1255);
+
+        let mut radio = Sx125x::new(MockChip::new([0; REGISTER_COUNT]), ICVersion::SX1255);
+        assert_eq!(radio.probe(), Err(Error::InvalidRegisterValue("version")));
+        assert_eq!(radio.ic_version(), ICVersion::SX1255);
+    }
+
+    #[test]
+    fn read_status_decodes_the_status_register() {
+        let mut registers = DEFAULT_REGISTERS_SX1257;
+        registers[RegisterAddr::Status.index()] = STATUS_XOSC_READY | STATUS_PLL_LOCK_TX;
+        let mut radio = Sx125x::new(MockChip::new(registers), ICVersion::SX1257);
+        let status = radio.read_status().unwrap();
+        assert_eq!(status.ic_version, ICVersion::SX1257);
+        assert!(status.oscillator_stable && status.transmit_pll_locked);
+        assert!(!status.receive_pll_locked && !status.battery_low);
+    }
+
+    #[test]
+    fn read_all_decodes_the_chip() {
+        let chip = MockChip::new(DEFAULT_REGISTERS_SX1255);
+        let mut radio = Sx125x::new(chip, ICVersion::SX1255);
+        assert_eq!(radio.read_all(), Ok(HardRegisters::default_for(ICVersion::SX1255)));
+    }
+
+    #[test]
+    fn read_control_round_trips_a_configuration() {
+        let control = control::ControlBuilder::new()
+            .ic_version(ICVersion::SX1255)
+            .mode(Mode::Receive)
+            .receive_frequency_mhz(434.0)
+            .build()
+            .unwrap();
+        let registers = control.to_hard_registers();
+        let mut bytes = [0u8; REGISTER_COUNT];
+        registers.serialize(&mut bytes, ICVersion::SX1255);
+        let mut radio = Sx125x::new(MockChip::new(bytes), ICVersion::SX1255);
+        let read = radio.read_control(control.crystal_frequency).unwrap();
+        assert_eq!(read.mode, Mode::Receive);
+        assert_eq!(read.ic_version, ICVersion::SX1255);
+        assert_eq!(read.to_hard_registers(), registers);
+    }
+
+    #[test]
+    fn write_all_skips_read_only_and_undocumented_registers() {
+        let mut radio = Sx125x::new(MockChip::new([0; REGISTER_COUNT]), ICVersion::SX1257);
+        radio.write_all(&HardRegisters::default_for(ICVersion::SX1257)).unwrap();
+        let chip = radio.release();
+        let addresses: Vec<u8> = chip.writes.iter().map(|(addr, _)| *addr).collect();
+        let mut expected: Vec<u8> = (0x00..=0x06).collect();
+        expected.extend([0x08, 0x0C, 0x0D, 0x0E, 0x0F, 0x10, 0x1A]);
+        assert_eq!(addresses, expected);
+        assert_eq!(chip.registers[0x01..=0x03], [0xC0, 0xE3, 0x8E]);
+    }
+
+    #[test]
+    fn write_diff_writes_only_changes() {
+        let old = HardRegisters::default_for(ICVersion::SX1255);
+        let mut new = HardRegisters::default_for(ICVersion::SX1255);
+        new.rx_frontend.lna_gain = 3;
+        let mut radio = Sx125x::new(MockChip::new(DEFAULT_REGISTERS_SX1255), ICVersion::SX1255);
+        radio.write_diff(&old, &new).unwrap();
+        assert_eq!(radio.read_all(), Ok(new));
+        assert_eq!(radio.release().writes.len(), 1);
+    }
+
+    #[test]
+    fn preset_is_written_by_name() {
+        let mut registers = HardRegisters::default_for(ICVersion::SX1255);
+        registers.rx_frontend.lna_gain = 4;
+        let mut presets = PresetTable::new();
+        presets.insert("agc low", registers.clone());
+
+        let mut radio = Sx125x::new(MockChip::new(DEFAULT_REGISTERS_SX1255), ICVersion::SX1255);
+        radio.write_all(presets.get("agc low").unwrap()).unwrap();
+        assert_eq!(radio.read_all(), Ok(registers));
+        assert!(presets.get("missing").is_none());
+    }
+
+    #[test]
+    fn reset_restores_the_defaults() {
+        for (ic, defaults) in [
+            (ICVersion::SX1255, DEFAULT_REGISTERS_SX1255),
+            (ICVersion::SX1257, DEFAULT_REGISTERS_SX1257),
+        ] {
+            let mut chip = MockChip::new([0xFF; REGISTER_COUNT]);
+            // The read-only registers report the chip's own values.
+            chip.registers[0x07] = defaults[0x07];
+            chip.registers[0x11] = defaults[0x11];
+            let mut radio = Sx125x::new(chip, ic);
+            radio.reset().unwrap();
+            assert_eq!(radio.read_all(), Ok(HardRegisters::default_for(ic)));
+
+            let chip = radio.release();
+            assert_eq!(chip.writes[0], (0x00, 0x00));
+            for (addr, value) in chip.writes {
+                assert_eq!(value, defaults[addr as usize]);
+            }
+        }
+    }
+
+    /// A delay that only counts the time asked for.
+    pub(crate) struct CountingDelay {
+        pub ns: u64,
+    }
+
+    impl DelayNs for CountingDelay {
+        fn delay_ns(&mut self, ns: u32) {
+            self.ns += ns as u64;
+        }
+    }
+
+    fn modes_written(chip: &MockChip) -> Vec<u8> {
+        chip.writes.iter().filter(|(addr, _)| *addr == 0x00).map(|(_, value)| *value).collect()
+    }
+
+    #[test]
+    fn set_mode_powers_up_in_order() {
+        let mut chip = MockChip::new([0; REGISTER_COUNT]);
+        chip.registers[0x11] = STATUS_XOSC_READY | STATUS_PLL_LOCK_RX | STATUS_PLL_LOCK_TX;
+        let mut radio = Sx125x::new(chip, ICVersion::SX1255);
+        let mut delay = CountingDelay { ns: 0 };
+        radio.set_mode(Mode::FullDuplex, &mut delay, Duration::from_micros(1000)).unwrap();
+        assert_eq!(modes_written(&radio.release()), [0x01, 0x03, 0x07, 0x0F]);
+        assert_eq!(delay.ns, 0);
+    }
+
+    #[test]
+    fn set_mode_powers_down_amplifier_first() {
+        let mut chip = MockChip::new([0; REGISTER_COUNT]);
+        chip.registers[0x00] = 0x0F;
+        let mut radio = Sx125x::new(chip, ICVersion::SX1255);
+        let timeout = Duration::from_micros(1000);
+        radio.set_mode(Mode::Receive, &mut CountingDelay { ns: 0 }, timeout).unwrap();
+        assert_eq!(modes_written(&radio.release()), [0x07, 0x03]);
+    }
+
+    #[test]
+    fn set_mode_waits_for_the_pll() {
+        // The oscillator is ready, but the transmit PLL never locks.
+        let mut chip = MockChip::new([0; REGISTER_COUNT]);
+        chip.registers[0x11] = STATUS_XOSC_READY;
+        let mut radio = Sx125x::new(chip, ICVersion::SX1257);
+        let mut delay = CountingDelay { ns: 0 };
+        let timeout = Duration::from_micros(200);
+        assert_eq!(radio.set_mode(Mode::Transmit, &mut delay, timeout), Err(Error::Timeout));
+        assert_eq!(delay.ns, 200_000);
+        // The amplifier is never enabled without a locked transmitter.
+        assert_eq!(modes_written(&radio.release()), [0x01, 0x05]);
+    }
+
+    #[test]
+    fn hop_writes_the_lsb_last() {
+        let mut radio = Sx125x::new(MockChip::new([0; REGISTER_COUNT]), ICVersion::SX1255);
+        let rx = Frequency::from_raw(0x123456);
+        let tx = Frequency::from_raw(0xABCDEF);
+        radio.hop_to(Some(&rx), Some(&tx)).unwrap();
+        let chip = radio.release();
+        assert_eq!(
+            chip.writes,
+            [(0x01, 0x12), (0x02, 0x34), (0x03, 0x56), (0x04, 0xAB), (0x05, 0xCD), (0x06, 0xEF)]
+        );
+
+        let mut radio = Sx125x::new(MockChip::new([0; REGISTER_COUNT]), ICVersion::SX1255);
+        radio.hop_to(None, Some(&tx)).unwrap();
+        assert_eq!(radio.release().writes.last(), Some(&(0x06, 0xEF)));
+    }
+
+    #[test]
+    fn scan_visits_each_channel_after_its_latch() {
+        let channels = [Frequency::from_raw(0xC0E38E), Frequency::from_raw(0xC0E48E)];
+        let mut radio = Sx125x::new(MockChip::new([0; REGISTER_COUNT]), ICVersion::SX1257);
+        let mut visited = Vec::new();
+        radio.scan(&channels, |channel| visited.push(channel.raw())).unwrap();
+        assert_eq!(visited, [0xC0E38E, 0xC0E48E]);
+        let chip = radio.release();
+        assert_eq!(chip.writes.len(), 6);
+        assert_eq!(chip.writes[2], (0x03, 0x8E));
+        assert_eq!(chip.registers[0x01..=0x03], [0xC0, 0xE4, 0x8E]);
+    }
+
+    #[test]
+    fn calibrate_iq_in_rf_loopback() {
+        let mut chip = MockChip::new([0; REGISTER_COUNT]);
+        chip.registers[0x10] = 0x02;
+        let mut radio = Sx125x::new(chip, ICVersion::SX1255);
+        let mut samples = [(0.0, 0.0); 512];
+        let calibration = radio
+            .calibrate_iq(&mut samples, |samples| {
+                let tone = crate::dsp::tests::imbalanced_tone(512, 19.0, 0.9, -0.1, (0.01, 0.0));
+                samples.copy_from_slice(&tone);
+                Ok(())
+            })
+            .unwrap();
+        assert!((calibration.gain_mismatch - 0.9).abs() < 1e-4);
+        assert!((calibration.phase_imbalance_rad - -0.1).abs() < 1e-4);
+        assert!((calibration.dc_offset_i - 0.01).abs() < 1e-4);
+        assert_eq!(radio.release().writes, [(0x10, 0x06), (0x10, 0x02)]);
+    }
+
+    #[test]
+    fn calibrate_iq_restores_clock_select_on_error() {
+        let mut chip = MockChip::new([0; REGISTER_COUNT]);
+        chip.registers[0x10] = 0x02;
+        let mut radio = Sx125x::new(chip, ICVersion::SX1255);
+        let result = radio.calibrate_iq(&mut [(0.0, 0.0); 16], |_| Err(Error::Timeout));
+        assert_eq!(result, Err(Error::Timeout));
+        let chip = radio.release();
+        assert_eq!(chip.registers[0x10], 0x02);
+        assert_eq!(chip.writes.last(), Some(&(0x10, 0x02)));
+    }
+
+    #[test]
+    fn set_clock_output_changes_only_its_bit() {
+        let mut chip = MockChip::new([0; REGISTER_COUNT]);
+        chip.registers[0x10] = 0x0D;
+        let mut radio = Sx125x::new(chip, ICVersion::SX1255);
+        radio.set_clock_output(true).unwrap();
+        radio.set_clock_output(false).unwrap();
+        assert_eq!(radio.release().writes, [(0x10, 0x0F), (0x10, 0x0D)]);
+    }
+
+    #[test]
+    fn calibrate_iq_without_signal_fails() {
+        let mut chip = MockChip::new([0; REGISTER_COUNT]);
+        chip.registers[0x10] = 0x02;
+        let mut radio = Sx125x::new(chip, ICVersion::SX1255);
+        let result = radio.calibrate_iq(&mut [(0.0, 0.0); 16], |_| Ok(()));
+        assert_eq!(result, Err(Error::NoSignal));
+        assert_eq!(radio.release().registers[0x10], 0x02);
+    }
+
+    #[test]
+    fn wait_pll_lock_polls_until_locked() {
+        let mut chip = MockChip::new([0; REGISTER_COUNT]);
+        chip.status_after_reads = Some((5, STATUS_PLL_LOCK_TX));
+        let mut radio = Sx125x::new(chip, ICVersion::SX1255);
+        let mut delay = CountingDelay { ns: 0 };
+        let timing = radio.wait_pll_lock(PllSelect::Tx, &mut delay, Duration::from_micros(1000));
+        assert_eq!(timing, Ok(LockTiming::Typical));
+        // Locked on the fifth read, reported by the sixth.
+        assert_eq!(delay.ns, 5 * 10_000);
+    }
+
+    #[test]
+    fn wait_pll_lock_reports_a_slow_lock() {
+        let mut chip = MockChip::new([0; REGISTER_COUNT]);
+        chip.status_after_reads = Some((20, STATUS_PLL_LOCK_RX));
+        let mut radio = Sx125x::new(chip, ICVersion::SX1255);
+        let mut delay = CountingDelay { ns: 0 };
+        let timing = radio.wait_pll_lock(PllSelect::Rx, &mut delay, Duration::from_micros(1000));
+        assert_eq!(timing, Ok(LockTiming::Slow(Duration::from_micros(200))));
+    }
+
+    #[test]
+    fn wait_pll_lock_never_locking_times_out() {
+        let mut radio = Sx125x::new(MockChip::new([0; REGISTER_COUNT]), ICVersion::SX1257);
+        let mut delay = CountingDelay { ns: 0 };
+        let timeout = Duration::from_micros(500);
+        for which in [PllSelect::Rx, PllSelect::Tx, PllSelect::Both] {
+            assert_eq!(radio.wait_pll_lock(which, &mut delay, timeout), Err(Error::Timeout));
+        }
+        assert_eq!(delay.ns, 3 * 500_000);
+    }
+
+    #[test]
+    fn wait_pll_lock_times_out() {
+        let mut chip = MockChip::new([0; REGISTER_COUNT]);
+        // The receiver locks, but full duplex needs both.
+        chip.registers[RegisterAddr::Status as usize] = STATUS_PLL_LOCK_RX;
+        let mut radio = Sx125x::new(chip, ICVersion::SX1255);
+        let mut delay = CountingDelay { ns: 0 };
+        let timeout = Duration::from_micros(150);
+        let result = radio.wait_pll_lock(PllSelect::Both, &mut delay, timeout);
+        assert_eq!(result, Err(Error::Timeout));
+        assert_eq!(delay.ns, 150_000);
+        let timing = radio.wait_pll_lock(PllSelect::Rx, &mut delay, timeout);
+        assert_eq!(timing, Ok(LockTiming::Typical));
+    }
+}
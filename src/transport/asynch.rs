@@ -0,0 +1,171 @@
+// Copyright (C) 2025 Bruce Perens
+// All Rights Reserved
+// This software is not presently under an Open Source license, I'll consider
+// what to do about that if someone pays me to do so, or when I'm done.
+
+//! The async counterpart of [Sx125x](super::Sx125x), for Embassy and other
+//! async runtimes. The wire protocol is shared with the blocking transport.
+
+use super::{
+    read_frame, write_frame, writes, LockTiming, PllSelect, POLL_INTERVAL_US, REGISTER_COUNT,
+};
+use crate::hard_registers::{HardRegisters, ICVersion, RegisterAddr};
+use crate::Error;
+use core::time::Duration;
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::spi::SpiDevice;
+
+/// An SX1255 or SX1257 on an async SPI bus.
+pub struct Sx125xAsync<SPI> {
+    spi: SPI,
+    ic_version: ICVersion,
+}
+
+impl<SPI: SpiDevice> Sx125xAsync<SPI> {
+    pub fn new(spi: SPI, ic_version: ICVersion) -> Self {
+        Sx125xAsync { spi, ic_version }
+    }
+
+    /// The IC version this transport was created for.
+    pub fn ic_version(&self) -> ICVersion {
+        self.ic_version
+    }
+
+    /// Give back the SPI device.
+    pub fn release(self) -> SPI {
+        self.spi
+    }
+
+    /// Read one register.
+    pub async fn read_register(&mut self, addr: u8) -> Result<u8, Error<SPI::Error>> {
+        let mut frame = read_frame(addr);
+        self.spi.transfer_in_place(&mut frame).await.map_err(Error::Spi)?;
+        Ok(frame[1])
+    }
+
+    /// Write one register.
+    pub async fn write_register(&mut self, addr: u8, value: u8) -> Result<(), Error<SPI::Error>> {
+        self.spi.write(&write_frame(addr, value)).await.map_err(Error::Spi)
+    }
+
+    /// Read the entire register bank. A register holding a code that
+    /// [HardRegisters::deserialize] doesn't describe fails with
+    /// [Error::InvalidRegisterValue].
+    pub async fn read_all(&mut self) -> Result<HardRegisters, Error<SPI::Error>> {
+        let mut bytes = [0u8; REGISTER_COUNT];
+        for (addr, byte) in bytes.iter_mut().enumerate() {
+            *byte = self.read_register(addr as u8).await?;
+        }
+        HardRegisters::deserialize(&bytes, self.ic_version).map_err(Error::into_bus_error)
+    }
+
+    /// Write the entire register bank, except for read-only registers and
+    /// those not documented for the IC.
+    pub async fn write_all(&mut self, registers: &HardRegisters) -> Result<(), Error<SPI::Error>> {
+        let mut bytes = [0u8; REGISTER_COUNT];
+        registers.serialize(&mut bytes, self.ic_version);
+        for (addr, byte) in writes(&bytes, self.ic_version) {
+            self.write_register(addr, byte).await?;
+        }
+        Ok(())
+    }
+
+    /// Wait for the selected synthesizers to lock, yielding to other tasks
+    /// between polls of the status register, and report how long it took
+    /// as [Sx125x::wait_pll_lock](super::Sx125x::wait_pll_lock) does. Fails
+    /// with [Error::Timeout] if there is no lock after `timeout`.
+    pub async fn wait_pll_lock<D: DelayNs>(
+        &mut self,
+        which: PllSelect,
+        delay: &mut D,
+        timeout: Duration,
+    ) -> Result<LockTiming, Error<SPI::Error>> {
+        let bits = which.status_bits();
+        let mut waited = Duration::ZERO;
+        loop {
+            if self.read_register(RegisterAddr::Status as u8).await? & bits == bits {
+                return Ok(LockTiming::after(waited));
+            }
+            if waited >= timeout {
+                return Err(Error::Timeout);
+            }
+            delay.delay_us(POLL_INTERVAL_US).await;
+            waited = waited.saturating_add(Duration::from_micros(POLL_INTERVAL_US.into()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hard_registers::DEFAULT_REGISTERS_SX1255;
+    use crate::transport::tests::{CountingDelay, MockChip};
+    use core::future::Future;
+    use core::pin::pin;
+    use core::task::{Context, Poll, Waker};
+    use embedded_hal::spi::Operation;
+
+    /// Run a future that never really waits, as the mocks don't.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut context = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+
+    impl SpiDevice for MockChip {
+        async fn transaction(
+            &mut self,
+            operations: &mut [Operation<'_, u8>],
+        ) -> Result<(), Self::Error> {
+            embedded_hal::spi::SpiDevice::transaction(self, operations)
+        }
+    }
+
+    impl DelayNs for CountingDelay {
+        async fn delay_ns(&mut self, ns: u32) {
+            self.ns += ns as u64;
+        }
+    }
+
+    #[test]
+    fn async_register_access() {
+        let chip = MockChip::new(DEFAULT_REGISTERS_SX1255);
+        let mut radio = Sx125xAsync::new(chip, ICVersion::SX1255);
+        block_on(async {
+            radio.write_register(0x0C, 0x55).await.unwrap();
+            assert_eq!(radio.read_register(0x0C).await, Ok(0x55));
+            radio.write_all(&HardRegisters::default_for(ICVersion::SX1255)).await.unwrap();
+            assert_eq!(radio.read_all().await, Ok(HardRegisters::default_for(ICVersion::SX1255)));
+        });
+        let chip = radio.release();
+        assert_eq!(chip.writes[0], (0x0C, 0x55));
+        // The same registers are written as by the blocking transport.
+        let blocking: Vec<(u8, u8)> =
+            writes(&DEFAULT_REGISTERS_SX1255, ICVersion::SX1255).collect();
+        assert_eq!(chip.writes[1..], blocking);
+    }
+
+    #[test]
+    fn async_wait_pll_lock() {
+        let mut chip = MockChip::new([0; REGISTER_COUNT]);
+        chip.registers[0x11] = 0x06;
+        let mut radio = Sx125xAsync::new(chip, ICVersion::SX1257);
+        let mut delay = CountingDelay { ns: 0 };
+        let timeout = Duration::from_micros(100);
+        block_on(async {
+            assert_eq!(
+                radio.wait_pll_lock(PllSelect::Rx, &mut delay, timeout).await,
+                Ok(LockTiming::Typical)
+            );
+            assert_eq!(
+                radio.wait_pll_lock(PllSelect::Both, &mut delay, timeout).await,
+                Err(Error::Timeout)
+            );
+        });
+        assert_eq!(delay.ns, 100_000);
+    }
+}